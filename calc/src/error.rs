@@ -0,0 +1,26 @@
+use std::fmt::{self, Display};
+
+#[derive(Debug)]
+pub enum Error {
+    UnexpectedChar(char),
+    InvalidNumber(String),
+    UnbalancedParentheses,
+    MissingOperand,
+    TrailingTokens,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnexpectedChar(c) => write!(f, "Error: unexpected character '{c}'"),
+            Error::InvalidNumber(s) => write!(f, "Error: invalid number literal '{s}'"),
+            Error::UnbalancedParentheses => write!(f, "Error: unbalanced parentheses"),
+            Error::MissingOperand => {
+                write!(f, "Error: expected a number or '('")
+            }
+            Error::TrailingTokens => {
+                write!(f, "Error: trailing tokens after a complete expression")
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::iter::Peekable;
+use std::{env, process::exit};
+
+use error::Error;
+
+mod error;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(f64),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    OpenParen,
+    CloseParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut iter = input.chars().peekable();
+
+    while let Some(ch) = iter.next() {
+        match ch {
+            '+' => tokens.push(Token::Plus),
+            '-' => tokens.push(Token::Minus),
+            '*' => tokens.push(Token::Star),
+            '/' => tokens.push(Token::Slash),
+            '(' => tokens.push(Token::OpenParen),
+            ')' => tokens.push(Token::CloseParen),
+            c if c.is_whitespace() => continue,
+            c if c.is_ascii_digit() => tokens.push(Token::Number(tokenize_number(c, &mut iter)?)),
+            _ => return Err(Error::UnexpectedChar(ch)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Tokenize a number literal: a run of digits, optionally followed by a
+/// `.` fraction with at least one digit.
+fn tokenize_number(first: char, iter: &mut Peekable<std::str::Chars<'_>>) -> Result<f64, Error> {
+    let mut s = String::new();
+    s.push(first);
+
+    while let Some(d) = iter.peek().copied() {
+        if !d.is_ascii_digit() {
+            break;
+        }
+        s.push(d);
+        iter.next();
+    }
+
+    if iter.peek() == Some(&'.') {
+        s.push('.');
+        iter.next();
+
+        let mut has_fraction_digit = false;
+        while let Some(d) = iter.peek().copied() {
+            if !d.is_ascii_digit() {
+                break;
+            }
+            has_fraction_digit = true;
+            s.push(d);
+            iter.next();
+        }
+
+        if !has_fraction_digit {
+            return Err(Error::InvalidNumber(s));
+        }
+    }
+
+    s.parse().map_err(|_| Error::InvalidNumber(s))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug)]
+enum Ast {
+    Num(f64),
+    BinOp(Op, Box<Ast>, Box<Ast>),
+}
+
+fn eval(ast: &Ast) -> f64 {
+    match ast {
+        Ast::Num(n) => *n,
+        Ast::BinOp(op, lhs, rhs) => {
+            let lhs = eval(lhs);
+            let rhs = eval(rhs);
+            match op {
+                Op::Add => lhs + rhs,
+                Op::Sub => lhs - rhs,
+                Op::Mul => lhs * rhs,
+                Op::Div => lhs / rhs,
+            }
+        }
+    }
+}
+
+/// Parse a full arithmetic expression, rejecting any tokens left over once
+/// the grammar is satisfied.
+fn parse(input: &str) -> Result<Ast, Error> {
+    let tokens = tokenize(input)?;
+    let mut iter = tokens.into_iter().peekable();
+
+    let ast = parse_expr(&mut iter)?;
+    if iter.peek().is_some() {
+        return Err(Error::TrailingTokens);
+    }
+
+    Ok(ast)
+}
+
+/// `expr = term (('+'|'-') term)*`
+fn parse_expr(tokens: &mut Peekable<std::vec::IntoIter<Token>>) -> Result<Ast, Error> {
+    let mut node = parse_term(tokens)?;
+    loop {
+        match tokens.peek() {
+            Some(Token::Plus) => {
+                tokens.next();
+                node = Ast::BinOp(Op::Add, Box::new(node), Box::new(parse_term(tokens)?));
+            }
+            Some(Token::Minus) => {
+                tokens.next();
+                node = Ast::BinOp(Op::Sub, Box::new(node), Box::new(parse_term(tokens)?));
+            }
+            _ => break,
+        }
+    }
+    Ok(node)
+}
+
+/// `term = factor (('*'|'/') factor)*`
+fn parse_term(tokens: &mut Peekable<std::vec::IntoIter<Token>>) -> Result<Ast, Error> {
+    let mut node = parse_factor(tokens)?;
+    loop {
+        match tokens.peek() {
+            Some(Token::Star) => {
+                tokens.next();
+                node = Ast::BinOp(Op::Mul, Box::new(node), Box::new(parse_factor(tokens)?));
+            }
+            Some(Token::Slash) => {
+                tokens.next();
+                node = Ast::BinOp(Op::Div, Box::new(node), Box::new(parse_factor(tokens)?));
+            }
+            _ => break,
+        }
+    }
+    Ok(node)
+}
+
+/// `factor = number | '(' expr ')'`
+fn parse_factor(tokens: &mut Peekable<std::vec::IntoIter<Token>>) -> Result<Ast, Error> {
+    match tokens.next() {
+        Some(Token::Number(n)) => Ok(Ast::Num(n)),
+        Some(Token::OpenParen) => {
+            let node = parse_expr(tokens)?;
+            match tokens.next() {
+                Some(Token::CloseParen) => Ok(node),
+                _ => Err(Error::UnbalancedParentheses),
+            }
+        }
+        Some(Token::CloseParen) => Err(Error::UnbalancedParentheses),
+        _ => Err(Error::MissingOperand),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("Please provide an expression to evaluate");
+        exit(1);
+    }
+    let expression = args.join(" ");
+
+    match parse(&expression) {
+        Ok(ast) => println!("{}", eval(&ast)),
+        Err(e) => {
+            eprintln!("{e}");
+            exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{eval, parse, Error};
+
+    fn run(expr: &str) -> f64 {
+        eval(&parse(expr).unwrap())
+    }
+
+    #[test]
+    fn test_single_number() {
+        assert_eq!(run("42"), 42.0);
+    }
+
+    #[test]
+    fn test_addition_and_subtraction() {
+        assert_eq!(run("1 + 2 - 3"), 0.0);
+    }
+
+    #[test]
+    fn test_multiplication_has_higher_precedence() {
+        assert_eq!(run("2 + 3 * 4"), 14.0);
+    }
+
+    #[test]
+    fn test_division() {
+        assert_eq!(run("10 / 4"), 2.5);
+    }
+
+    #[test]
+    fn test_parentheses_override_precedence() {
+        assert_eq!(run("(2 + 3) * 4"), 20.0);
+    }
+
+    #[test]
+    fn test_nested_parentheses() {
+        assert_eq!(run("2 * (3 + (4 - 1))"), 12.0);
+    }
+
+    #[test]
+    fn test_fraction_literal() {
+        assert_eq!(run("1.5 + 2.25"), 3.75);
+    }
+
+    #[test]
+    fn test_whitespace_is_ignored() {
+        assert_eq!(run("  1+  2 *3 "), 7.0);
+    }
+
+    #[test]
+    fn test_unclosed_parenthesis_is_an_error() {
+        assert!(matches!(
+            parse("(1 + 2"),
+            Err(Error::UnbalancedParentheses)
+        ));
+    }
+
+    #[test]
+    fn test_stray_closing_parenthesis_is_an_error() {
+        assert!(matches!(parse("1 + 2)"), Err(Error::TrailingTokens)));
+        assert!(matches!(parse(")"), Err(Error::UnbalancedParentheses)));
+    }
+
+    #[test]
+    fn test_trailing_tokens_are_an_error() {
+        assert!(matches!(parse("1 + 2 3"), Err(Error::TrailingTokens)));
+    }
+
+    #[test]
+    fn test_missing_operand_is_an_error() {
+        assert!(matches!(parse("1 +"), Err(Error::MissingOperand)));
+        assert!(matches!(parse(""), Err(Error::MissingOperand)));
+    }
+
+    #[test]
+    fn test_bare_trailing_dot_is_an_error() {
+        assert!(matches!(parse("1."), Err(Error::InvalidNumber(_))));
+    }
+
+    #[test]
+    fn test_unexpected_character_is_an_error() {
+        assert!(matches!(parse("1 & 2"), Err(Error::UnexpectedChar('&'))));
+    }
+}
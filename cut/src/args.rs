@@ -15,10 +15,33 @@ impl Read for Input {
     }
 }
 
+/// A single `-f`/`-b`/`-c` selector: an inclusive range of 1-based column
+/// numbers, with either end left open (`2-`, `-4`) to mean "to the end of
+/// the line" or "from the start of the line".
+pub struct FieldRange {
+    start: Option<usize>,
+    end: Option<usize>,
+}
+
+impl FieldRange {
+    pub fn contains(&self, col: usize) -> bool {
+        self.start.map_or(true, |s| col >= s) && self.end.map_or(true, |e| col <= e)
+    }
+}
+
+/// What a selected column number refers to.
+pub enum Mode {
+    Fields,
+    Bytes,
+    Chars,
+}
+
 pub struct Args {
     pub input: BufReader<Input>,
     pub sep: char,
-    pub fields: Vec<usize>,
+    pub fields: Vec<FieldRange>,
+    pub mode: Mode,
+    pub complement: bool,
 }
 
 impl Args {
@@ -26,24 +49,23 @@ impl Args {
         let mut iter = args.iter().skip(1).peekable();
         let mut input = Input::Stdin;
         let mut sep = '\t';
-        let mut fields: Vec<usize> = vec![];
+        let mut fields: Vec<FieldRange> = vec![];
+        let mut mode = Mode::Fields;
+        let mut complement = false;
         while let Some(arg) = iter.next() {
-            if arg.starts_with("-f") {
+            if arg.starts_with("-f") || arg.starts_with("-b") || arg.starts_with("-c") {
+                mode = match &arg[..2] {
+                    "-b" => Mode::Bytes,
+                    "-c" => Mode::Chars,
+                    _ => Mode::Fields,
+                };
                 if arg.len() > 2 {
-                    // Parse comma separated values
-                    arg.clone()
-                        .split_off(2)
-                        .split(",")
-                        .for_each(|v| fields.push(v.parse().expect(&usage("Invalid col value"))));
+                    fields.extend(parse_field_list(&arg[2..]));
+                } else if let Some(arg) = iter.next() {
+                    arg.split(" ")
+                        .for_each(|v| fields.extend(parse_field_list(v)));
                 } else {
-                    // Parse whitespace separated values
-                    if let Some(arg) = iter.next() {
-                        arg.clone().split(" ").for_each(|v| {
-                            fields.push(v.parse().expect(&usage("Invalid col value")))
-                        });
-                    } else {
-                        panic!("{}", &usage("Expect a list a values after -f"))
-                    }
+                    panic!("{}", &usage("Expect a list a values after -f"))
                 }
             } else if arg.starts_with("-d") {
                 sep = arg
@@ -52,6 +74,8 @@ impl Args {
                     .chars()
                     .next()
                     .expect(&usage("Please provide a char after -d"))
+            } else if arg == "--complement" {
+                complement = true
             } else if arg == "-" {
                 input = Input::Stdin
             } else {
@@ -65,17 +89,106 @@ impl Args {
             input: BufReader::new(input),
             sep,
             fields,
+            mode,
+            complement,
         }
     }
 }
 
+/// Parse a comma-separated selector list such as `1,3-5,7-` or `-4` into
+/// ranges, expanding each entry without needing to know the line length.
+fn parse_field_list(s: &str) -> Vec<FieldRange> {
+    s.split(",")
+        .map(|part| {
+            if let Some((start, end)) = part.split_once('-') {
+                FieldRange {
+                    start: if start.is_empty() {
+                        None
+                    } else {
+                        Some(start.parse().expect(&usage("Invalid col value")))
+                    },
+                    end: if end.is_empty() {
+                        None
+                    } else {
+                        Some(end.parse().expect(&usage("Invalid col value")))
+                    },
+                }
+            } else {
+                let n = part.parse().expect(&usage("Invalid col value"));
+                FieldRange {
+                    start: Some(n),
+                    end: Some(n),
+                }
+            }
+        })
+        .collect()
+}
+
 fn usage(error: &str) -> String {
     format!(
         "Error: {error}
 Usage: cut <option> <filename>
     options:
-        -f[a,b,...,c] | -f [\"a b c\"] : Choose cols to extract
+        -f[a,b-c,...] | -f [\"a b c\"] : Choose fields to extract (ranges like 1-3, 2-, -4 allowed)
+        -b[a,b-c,...] : Choose byte offsets to extract instead of fields
+        -c[a,b-c,...] : Choose char offsets to extract instead of fields
         -d[ch]: Set the char delimiter to be ch
+        --complement: Output every column except the selected ones
 \n"
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(arg: &str) -> Args {
+        Args::parse(vec!["cut".to_string(), arg.to_string()])
+    }
+
+    #[test]
+    fn parses_basic_field_list() {
+        let args = parse("-f1,3");
+        assert!(args.fields.iter().any(|r| r.contains(1)));
+        assert!(!args.fields.iter().any(|r| r.contains(2)));
+        assert!(args.fields.iter().any(|r| r.contains(3)));
+    }
+
+    #[test]
+    fn parses_open_start_range() {
+        let args = parse("-f-3");
+        assert!(args.fields.iter().any(|r| r.contains(1)));
+        assert!(args.fields.iter().any(|r| r.contains(3)));
+        assert!(!args.fields.iter().any(|r| r.contains(4)));
+    }
+
+    #[test]
+    fn parses_open_end_range() {
+        let args = parse("-f3-");
+        assert!(!args.fields.iter().any(|r| r.contains(2)));
+        assert!(args.fields.iter().any(|r| r.contains(3)));
+        assert!(args.fields.iter().any(|r| r.contains(100)));
+    }
+
+    #[test]
+    fn complement_flag_is_recorded() {
+        let args = Args::parse(vec![
+            "cut".to_string(),
+            "-f1".to_string(),
+            "--complement".to_string(),
+        ]);
+        assert!(args.complement);
+    }
+
+    #[test]
+    fn selects_byte_mode() {
+        let args = parse("-b1-3");
+        assert!(matches!(args.mode, Mode::Bytes));
+    }
+
+    #[test]
+    fn selects_char_mode() {
+        let args = parse("-c2");
+        assert!(matches!(args.mode, Mode::Chars));
+    }
+}
@@ -1,9 +1,52 @@
-use std::{env, io::BufRead};
+use std::{
+    env,
+    io::{stdout, BufRead, Write},
+};
 
-use args::Args;
+use args::{Args, FieldRange, Mode};
 
 mod args;
 
+/// Is 1-based column `col` part of this invocation's output: selected by
+/// any configured range, inverted if `--complement` was given.
+fn is_selected(fields: &[FieldRange], complement: bool, col: usize) -> bool {
+    fields.iter().any(|r| r.contains(col)) != complement
+}
+
+fn cut_fields(line: &str, sep: char, fields: &[FieldRange], complement: bool) -> String {
+    let mut out = String::new();
+    let mut first = true;
+    for (i, val) in line.split(sep).enumerate() {
+        if is_selected(fields, complement, i + 1) {
+            if first {
+                out.push_str(val);
+                first = false;
+            } else {
+                out.push('\t');
+                out.push_str(val);
+            }
+        }
+    }
+    out
+}
+
+fn cut_chars(line: &str, fields: &[FieldRange], complement: bool) -> String {
+    line.chars()
+        .enumerate()
+        .filter(|(i, _)| is_selected(fields, complement, i + 1))
+        .map(|(_, ch)| ch)
+        .collect()
+}
+
+fn cut_bytes(line: &str, fields: &[FieldRange], complement: bool) -> Vec<u8> {
+    line.as_bytes()
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| is_selected(fields, complement, i + 1))
+        .map(|(_, byte)| *byte)
+        .collect()
+}
+
 fn main() {
     let mut args = Args::parse(env::args().collect());
 
@@ -12,19 +55,64 @@ fn main() {
         if bytes_read == 0 {
             break;
         }
+        let line = buf.strip_suffix('\n').unwrap_or(&buf);
 
-        let mut col = 1;
-        for val in buf.split(args.sep) {
-            if args.fields.contains(&col) {
-                if col == 1 {
-                    print!("{val}")
-                } else {
-                    print!("\t{val}")
-                }
+        match args.mode {
+            Mode::Fields => print!("{}", cut_fields(line, args.sep, &args.fields, args.complement)),
+            Mode::Chars => print!("{}", cut_chars(line, &args.fields, args.complement)),
+            Mode::Bytes => {
+                let selected = cut_bytes(line, &args.fields, args.complement);
+                stdout().write_all(&selected).unwrap();
             }
-            col += 1;
         }
         print!("\n");
         buf.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_fields(spec: &str) -> Vec<FieldRange> {
+        Args::parse(vec!["cut".to_string(), spec.to_string()]).fields
+    }
+
+    #[test]
+    fn cut_fields_selects_listed_columns() {
+        let fields = parse_fields("-f1,3");
+        assert_eq!(cut_fields("a\tb\tc", '\t', &fields, false), "a\tc");
+    }
+
+    #[test]
+    fn cut_fields_open_range_to_end() {
+        let fields = parse_fields("-f2-");
+        assert_eq!(cut_fields("a\tb\tc\td", '\t', &fields, false), "b\tc\td");
+    }
+
+    #[test]
+    fn cut_fields_open_range_from_start() {
+        let fields = parse_fields("-f-2");
+        assert_eq!(cut_fields("a\tb\tc\td", '\t', &fields, false), "a\tb");
+    }
+
+    #[test]
+    fn cut_fields_complement_inverts_selection() {
+        let fields = parse_fields("-f2");
+        assert_eq!(cut_fields("a\tb\tc", '\t', &fields, true), "a\tc");
+    }
+
+    #[test]
+    fn cut_chars_selects_by_character_not_byte() {
+        let fields = parse_fields("-c1-3");
+        assert_eq!(cut_chars("héllo", &fields, false), "hél");
+    }
+
+    #[test]
+    fn cut_bytes_selects_raw_bytes_even_mid_multibyte_char() {
+        let fields = parse_fields("-b1-4");
+        // "café" is 63 61 66 c3 a9 in UTF-8; bytes 1-4 cut mid-character.
+        let selected = cut_bytes("café", &fields, false);
+        assert_eq!(selected, vec![b'c', b'a', b'f', 0xc3]);
+    }
+}
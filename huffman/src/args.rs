@@ -9,6 +9,7 @@ pub struct Args {
     pub input: String,
     pub output: String,
     pub mode: Mode,
+    pub block: bool,
 }
 
 impl Args {
@@ -17,6 +18,7 @@ impl Args {
         let mut in_file = None;
         let mut out_file = None;
         let mut mode = Mode::Compress;
+        let mut block = false;
 
         let mut iter = args.iter();
         while let Some(arg) = iter.next() {
@@ -24,6 +26,7 @@ impl Args {
                 match arg.as_str() {
                     "-c" => mode = Mode::Compress,
                     "-u" => mode = Mode::Uncompress,
+                    "-b" => block = true,
                     "-o" => match iter.next() {
                         Some(s) => out_file = Some(s.to_string()),
                         None => return Err(Error::UsingOWithoutFile),
@@ -40,6 +43,7 @@ impl Args {
                 input: filename,
                 output: out_file.map_or("a.out".to_string(), |s| s),
                 mode,
+                block,
             }),
             None => Err(Error::NoFileProvided),
         }
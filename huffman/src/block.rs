@@ -0,0 +1,289 @@
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+
+use crate::error::Error;
+use crate::huffman::{self, HuffmanTree};
+use crate::{get_frequencies, write_decoded_file, write_encoded_file};
+
+/// Format flag written as the very first byte of a compressed file,
+/// distinguishing the original single-stream format from block mode.
+pub(crate) const FORMAT_SINGLE_STREAM: u8 = 0;
+pub(crate) const FORMAT_BLOCK: u8 = 1;
+
+/// Size of each independently-compressed window, in bytes of source text.
+pub const BLOCK_SIZE: usize = 64 * 1024;
+
+/// One entry of the footer index: where a block's original bytes sit in
+/// the uncompressed stream, and where its compressed form sits in the file.
+struct BlockEntry {
+    uncompressed_offset: u64,
+    uncompressed_len: u64,
+    compressed_offset: u64,
+    compressed_len: u64,
+}
+
+/// Compress `input` in block mode: split it into `BLOCK_SIZE`-byte windows,
+/// Huffman-encode each one independently (its own canonical header, its
+/// own byte-aligned bitstream), then append a footer index mapping each
+/// block's uncompressed offset to where its compressed bytes live. A
+/// reader can later seek straight to the blocks it needs instead of
+/// decoding the whole file.
+pub fn compress<R: Read, W: Write + Seek>(input: &mut R, output: &mut W) -> Result<(), Error> {
+    output
+        .write_all(&[FORMAT_BLOCK])
+        .map_err(|_| Error::FileWriting)?;
+
+    let mut entries = Vec::new();
+    let mut uncompressed_offset = 0u64;
+
+    loop {
+        let mut block = Vec::new();
+        input
+            .by_ref()
+            .take(BLOCK_SIZE as u64)
+            .read_to_end(&mut block)
+            .map_err(|_| Error::FileUnreadable)?;
+        let filled = block.len();
+
+        if block.is_empty() {
+            break;
+        }
+
+        let compressed_offset = output.stream_position().map_err(|_| Error::FileWriting)?;
+
+        let (freqs, original_size) = get_frequencies(&mut Cursor::new(&block))?;
+        let tree = HuffmanTree::build_huffman(freqs).ok_or(Error::EmptyFile)?;
+        let mut lengths = HuffmanTree::code_lengths(&tree);
+        HuffmanTree::limit_code_lengths(&mut lengths, huffman::MAX_CODE_LENGTH);
+        let canonical_tree = HuffmanTree::from_code_lengths(&lengths);
+
+        canonical_tree
+            .write_to(output)
+            .map_err(|_| Error::FileWriting)?;
+        write_encoded_file(&canonical_tree, &mut Cursor::new(&block), output, original_size)?;
+
+        let compressed_end = output.stream_position().map_err(|_| Error::FileWriting)?;
+
+        entries.push(BlockEntry {
+            uncompressed_offset,
+            uncompressed_len: block.len() as u64,
+            compressed_offset,
+            compressed_len: compressed_end - compressed_offset,
+        });
+
+        uncompressed_offset += block.len() as u64;
+
+        if filled < BLOCK_SIZE {
+            break;
+        }
+    }
+
+    write_footer(output, &entries)
+}
+
+fn write_footer<W: Write + Seek>(output: &mut W, entries: &[BlockEntry]) -> Result<(), Error> {
+    let footer_offset = output.stream_position().map_err(|_| Error::FileWriting)?;
+
+    output
+        .write_all(&(entries.len() as u32).to_le_bytes())
+        .map_err(|_| Error::FileWriting)?;
+    for entry in entries {
+        output
+            .write_all(&entry.uncompressed_offset.to_le_bytes())
+            .map_err(|_| Error::FileWriting)?;
+        output
+            .write_all(&entry.uncompressed_len.to_le_bytes())
+            .map_err(|_| Error::FileWriting)?;
+        output
+            .write_all(&entry.compressed_offset.to_le_bytes())
+            .map_err(|_| Error::FileWriting)?;
+        output
+            .write_all(&entry.compressed_len.to_le_bytes())
+            .map_err(|_| Error::FileWriting)?;
+    }
+
+    // A trailing pointer to the footer lets a reader find the index by
+    // seeking from the end of the file, without scanning every block.
+    output
+        .write_all(&footer_offset.to_le_bytes())
+        .map_err(|_| Error::FileWriting)
+}
+
+fn read_footer<R: Read + Seek>(input: &mut R) -> Result<Vec<BlockEntry>, Error> {
+    input
+        .seek(SeekFrom::End(-8))
+        .map_err(|_| Error::FileReading)?;
+    let mut footer_offset = [0u8; 8];
+    input
+        .read_exact(&mut footer_offset)
+        .map_err(|_| Error::FileReading)?;
+
+    input
+        .seek(SeekFrom::Start(u64::from_le_bytes(footer_offset)))
+        .map_err(|_| Error::FileReading)?;
+
+    let mut count = [0u8; 4];
+    input.read_exact(&mut count).map_err(|_| Error::FileReading)?;
+    let count = u32::from_le_bytes(count);
+
+    let mut read_u64 = |input: &mut R| -> Result<u64, Error> {
+        let mut buf = [0u8; 8];
+        input.read_exact(&mut buf).map_err(|_| Error::FileReading)?;
+        Ok(u64::from_le_bytes(buf))
+    };
+
+    let mut entries = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        entries.push(BlockEntry {
+            uncompressed_offset: read_u64(input)?,
+            uncompressed_len: read_u64(input)?,
+            compressed_offset: read_u64(input)?,
+            compressed_len: read_u64(input)?,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Decode only the blocks overlapping the uncompressed byte range
+/// `[start, end)`, writing just that slice to `output`. Binary-searches
+/// the footer index for the first relevant block, so callers can pull an
+/// arbitrary region out of a large compressed file without scanning the
+/// whole stream.
+pub fn decompress_range<R: Read + Seek, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    start: u64,
+    end: u64,
+) -> Result<(), Error> {
+    let mut flag = [0u8; 1];
+    input
+        .seek(SeekFrom::Start(0))
+        .map_err(|_| Error::FileReading)?;
+    input.read_exact(&mut flag).map_err(|_| Error::FileReading)?;
+    if flag[0] != FORMAT_BLOCK {
+        return Err(Error::InvalidFile);
+    }
+
+    let entries = read_footer(input)?;
+    let first = entries.partition_point(|e| e.uncompressed_offset + e.uncompressed_len <= start);
+
+    for entry in &entries[first..] {
+        if entry.uncompressed_offset >= end {
+            break;
+        }
+
+        input
+            .seek(SeekFrom::Start(entry.compressed_offset))
+            .map_err(|_| Error::FileReading)?;
+        let mut block_reader = input.by_ref().take(entry.compressed_len);
+
+        let canonical_tree =
+            HuffmanTree::read_from(&mut block_reader).map_err(|_| Error::FileReading)?;
+
+        let mut decoded = Vec::new();
+        write_decoded_file(&canonical_tree, &mut block_reader, &mut decoded)?;
+
+        let local_start = start.saturating_sub(entry.uncompressed_offset) as usize;
+        let local_end = (end - entry.uncompressed_offset).min(decoded.len() as u64) as usize;
+        if local_start < local_end {
+            output
+                .write_all(&decoded[local_start..local_end])
+                .map_err(|_| Error::FileWriting)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Is this file (or the start of this stream) using block mode? Leaves the
+/// stream positioned right after the flag byte, where the single-stream
+/// header reader expects to start.
+pub fn is_block_mode<R: Read + Seek>(input: &mut R) -> Result<bool, Error> {
+    input
+        .seek(SeekFrom::Start(0))
+        .map_err(|_| Error::FileReading)?;
+    let mut flag = [0u8; 1];
+    input.read_exact(&mut flag).map_err(|_| Error::FileReading)?;
+    Ok(flag[0] == FORMAT_BLOCK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decompress_range, is_block_mode, BLOCK_SIZE};
+    use std::io::Cursor;
+
+    /// A repeating, multi-character source long enough to span several
+    /// blocks, so compression and range decoding both exercise block
+    /// boundaries instead of just the single-block case.
+    fn big_text() -> Vec<u8> {
+        "the quick brown fox jumps over the lazy dog. "
+            .bytes()
+            .cycle()
+            .take(BLOCK_SIZE * 2 + 137)
+            .collect()
+    }
+
+    #[test]
+    fn test_compress_flags_block_mode() {
+        let text = big_text();
+        let mut compressed = Cursor::new(Vec::new());
+        compress(&mut Cursor::new(&text), &mut compressed).unwrap();
+
+        assert!(is_block_mode(&mut compressed).unwrap());
+    }
+
+    #[test]
+    fn test_decompress_range_full_file_roundtrips() {
+        let text = big_text();
+        let mut compressed = Cursor::new(Vec::new());
+        compress(&mut Cursor::new(&text), &mut compressed).unwrap();
+
+        let mut decoded = Vec::new();
+        decompress_range(&mut compressed, &mut decoded, 0, text.len() as u64).unwrap();
+
+        assert_eq!(decoded, text);
+    }
+
+    #[test]
+    fn test_decompress_range_spans_a_block_boundary() {
+        let text = big_text();
+        let mut compressed = Cursor::new(Vec::new());
+        compress(&mut Cursor::new(&text), &mut compressed).unwrap();
+
+        let start = BLOCK_SIZE as u64 - 10;
+        let end = BLOCK_SIZE as u64 + 10;
+
+        let mut decoded = Vec::new();
+        decompress_range(&mut compressed, &mut decoded, start, end).unwrap();
+
+        assert_eq!(decoded, text[start as usize..end as usize]);
+    }
+
+    /// A Fibonacci-weighted repeat count per distinct byte is the classic
+    /// worst case for Huffman tree depth, forcing `limit_code_lengths` to
+    /// kick in on a block's header — unlike `big_text`'s low-entropy
+    /// repeating fixture, whose natural code depth never gets that deep.
+    fn skewed_block() -> Vec<u8> {
+        let mut data = Vec::new();
+        let (mut a, mut b) = (1u32, 1u32);
+        for i in 0..24u8 {
+            data.extend(std::iter::repeat(i).take(a as usize));
+            let next = a + b;
+            a = b;
+            b = next;
+        }
+        data
+    }
+
+    #[test]
+    fn test_compress_block_with_skewed_symbol_distribution_roundtrips() {
+        let text = skewed_block();
+        let mut compressed = Cursor::new(Vec::new());
+        compress(&mut Cursor::new(&text), &mut compressed).unwrap();
+
+        let mut decoded = Vec::new();
+        decompress_range(&mut compressed, &mut decoded, 0, text.len() as u64).unwrap();
+
+        assert_eq!(decoded, text);
+    }
+}
@@ -3,8 +3,10 @@ use std::fmt::Display;
 #[derive(Debug, Clone)]
 pub enum Error {
     FileUnreadable,
+    FileReading,
     FileWriting,
-    NotEnoughDifferentChars,
+    InvalidFile,
+    EmptyFile,
     UsingOWithoutFile,
     BadOption,
     NoFileProvided,
@@ -14,10 +16,9 @@ impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::FileUnreadable => write!(f, "The provided file is unreadable"),
-            Error::NotEnoughDifferentChars => write!(
-                f,
-                "To be compressed, the file needs at least 2 distinct characters"
-            ),
+            Error::FileReading => write!(f, "An error occured while reading the input file"),
+            Error::InvalidFile => write!(f, "The input file is not a valid compressed file"),
+            Error::EmptyFile => write!(f, "The file is empty, there is nothing to compress"),
             Error::UsingOWithoutFile => {
                 write!(f, "You must provide a filename if you use option -o.")
             }
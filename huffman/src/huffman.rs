@@ -1,29 +1,44 @@
 use std::{
     cmp::{Ordering, Reverse},
     collections::BinaryHeap,
+    hash::Hash,
+    io::{self, Read, Write},
 };
 
 use crate::HashMap;
 
+/// Codes are stored as one length byte per symbol in the file header, so no
+/// symbol's canonical code may be longer than this.
+pub const MAX_CODE_LENGTH: u8 = 15;
+
 #[derive(Clone, Debug)]
-enum HuffmanNode {
+enum HuffmanNode<T> {
     Leaf {
-        element: char,
+        element: T,
         weight: u32,
     },
     Internal {
         weight: u32,
-        left: Box<HuffmanNode>,
-        right: Box<HuffmanNode>,
+        left: Box<HuffmanNode<T>>,
+        right: Box<HuffmanNode<T>>,
     },
 }
 
-impl HuffmanNode {
-    fn newaf(element: char, weight: u32) -> HuffmanNode {
+impl<T> HuffmanNode<T> {
+    fn weight(&self) -> u32 {
+        match self {
+            Self::Leaf { weight, .. } => *weight,
+            Self::Internal { weight, .. } => *weight,
+        }
+    }
+}
+
+impl<T: Clone> HuffmanNode<T> {
+    fn newaf(element: T, weight: u32) -> HuffmanNode<T> {
         HuffmanNode::Leaf { element, weight }
     }
 
-    fn new_internal(left: HuffmanNode, right: HuffmanNode, weight: u32) -> HuffmanNode {
+    fn new_internal(left: HuffmanNode<T>, right: HuffmanNode<T>, weight: u32) -> HuffmanNode<T> {
         HuffmanNode::Internal {
             weight,
             left: Box::new(left),
@@ -31,15 +46,8 @@ impl HuffmanNode {
         }
     }
 
-    fn weight(&self) -> u32 {
-        match self {
-            Self::Leaf { weight, .. } => *weight,
-            Self::Internal { weight, .. } => *weight,
-        }
-    }
-
     #[allow(dead_code)]
-    fn left(&self) -> Option<HuffmanNode> {
+    fn left(&self) -> Option<HuffmanNode<T>> {
         match self {
             Self::Leaf { .. } => None,
             Self::Internal { left, .. } => Some(*left.clone()),
@@ -47,7 +55,7 @@ impl HuffmanNode {
     }
 
     #[allow(dead_code)]
-    fn right(&self) -> Option<HuffmanNode> {
+    fn right(&self) -> Option<HuffmanNode<T>> {
         match self {
             Self::Leaf { .. } => None,
             Self::Internal { right, .. } => Some(*right.clone()),
@@ -55,7 +63,7 @@ impl HuffmanNode {
     }
 
     #[allow(dead_code)]
-    fn elem(&self) -> Option<char> {
+    fn elem(&self) -> Option<T> {
         match self {
             Self::Leaf { element, .. } => Some(element.clone()),
             Self::Internal { .. } => None,
@@ -64,40 +72,117 @@ impl HuffmanNode {
 }
 
 #[derive(Debug)]
-pub struct HuffmanTree {
-    root: HuffmanNode,
+pub struct HuffmanTree<T> {
+    root: HuffmanNode<T>,
 }
 
-impl PartialEq for HuffmanTree {
+impl<T> PartialEq for HuffmanTree<T> {
     fn eq(&self, other: &Self) -> bool {
         self.root.weight() == other.root.weight()
     }
 }
 
-impl PartialOrd for HuffmanTree {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+impl<T> PartialOrd for HuffmanTree<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.root.weight().cmp(&other.root.weight()))
     }
 }
 
-impl Eq for HuffmanTree {}
+impl<T> Eq for HuffmanTree<T> {}
 
-impl Ord for HuffmanTree {
+impl<T> Ord for HuffmanTree<T> {
     fn cmp(&self, other: &Self) -> Ordering {
         self.root.weight().cmp(&other.root.weight())
     }
 }
 
-impl HuffmanTree {
-    pub fn build_huffman(freq: HashMap<char, u32>) -> Option<HuffmanTree> {
-        if freq.len() < 2 {
+/// One entry of a [`CompiledDecoder`] jump table.
+#[derive(Debug, Clone, Copy)]
+pub enum Decode<T> {
+    /// A leaf was reached within this table's `k`-bit chunk: `symbol` is
+    /// the decoded symbol, and `bits_consumed` (`<= k`) is how many of the
+    /// chunk's bits actually belonged to its code — the rest are the start
+    /// of the next code and must not be skipped.
+    Done { symbol: T, bits_consumed: u8 },
+    /// The `k`-bit chunk wasn't enough to reach a leaf: continue decoding
+    /// from `tables[next_table]`, an entire `k` bits further into the tree.
+    Continue { next_table: usize },
+}
+
+/// A table-driven decoder for a [`HuffmanTree`], built by
+/// [`HuffmanTree::compile_decoder`]. Consumes `k` bits per lookup instead
+/// of descending the tree one bit at a time, trading a precomputed table
+/// (`2^k` entries per table, one table per tree node visited while
+/// building it) for faster decoding.
+pub struct CompiledDecoder<T> {
+    k: u8,
+    tables: Vec<Vec<Decode<T>>>,
+}
+
+impl<T: Clone> CompiledDecoder<T> {
+    /// Decode `bytes` using this table, stopping after `bit_len` bits —
+    /// exactly like [`HuffmanTree::decode`], but resolving each symbol
+    /// with one table lookup per `k`-bit chunk instead of one per bit.
+    pub fn decode_fast(&self, bytes: &[u8], bit_len: usize) -> Vec<T> {
+        let mut result = Vec::new();
+        let mut table_index = 0;
+        let mut bit_pos = 0;
+
+        while bit_pos < bit_len {
+            let combo = peek_bits(bytes, bit_pos, bit_len, self.k);
+            match &self.tables[table_index][combo] {
+                Decode::Done {
+                    symbol,
+                    bits_consumed,
+                } => {
+                    result.push(symbol.clone());
+                    bit_pos += *bits_consumed as usize;
+                    table_index = 0;
+                }
+                Decode::Continue { next_table } => {
+                    bit_pos += self.k as usize;
+                    table_index = *next_table;
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// Read the `k`-bit value starting at bit offset `bit_pos` (MSB-first,
+/// 0-indexed from the start of `bytes`), zero-padding past `bit_len` so a
+/// short final chunk can still be looked up without reading out of bounds.
+fn peek_bits(bytes: &[u8], bit_pos: usize, bit_len: usize, k: u8) -> usize {
+    let mut value = 0usize;
+    for offset in 0..k as usize {
+        let pos = bit_pos + offset;
+        let bit = if pos < bit_len {
+            (bytes[pos / 8] >> (7 - pos % 8)) & 1
+        } else {
+            0
+        };
+        value = (value << 1) | bit as usize;
+    }
+    value
+}
+
+impl<T: Clone + Ord + Hash> HuffmanTree<T> {
+    pub fn build_huffman(freq: HashMap<T, u32>) -> Option<HuffmanTree<T>> {
+        if freq.is_empty() {
             return None;
         }
 
+        if freq.len() == 1 {
+            return Some(HuffmanTree {
+                root: single_symbol_node(freq.into_iter().next().unwrap().0),
+            });
+        }
+
         let mut heap = BinaryHeap::new();
 
         let mut pairs = freq.into_iter().collect::<Vec<_>>();
-        pairs.sort_by_key(|(key, _)| *key);
+        pairs.sort_by_key(|(key, _)| key.clone());
 
         for (elem, weight) in pairs {
             heap.push(Reverse(HuffmanTree {
@@ -121,62 +206,438 @@ impl HuffmanTree {
         Some(heap.pop().unwrap().0)
     }
 
-    pub fn gen_char_code_map(tree: HuffmanTree) -> HashMap<char, String> {
+    pub fn gen_symbol_code_map(&self) -> HashMap<T, String> {
         let mut codes = HashMap::new();
-        HuffmanTree::rec_gen_char_code_map(&tree.root, &mut String::new(), &mut codes);
+        HuffmanTree::rec_gen_symbol_code_map(&self.root, &mut String::new(), &mut codes);
 
         codes
     }
 
-    pub fn gen_code_char_map(tree: HuffmanTree) -> HashMap<String, char> {
+    #[allow(dead_code)]
+    pub fn gen_code_symbol_map(&self) -> HashMap<String, T> {
         let mut codes = HashMap::new();
 
-        HuffmanTree::rec_gen_code_char_map(&tree.root, &mut String::new(), &mut codes);
+        HuffmanTree::rec_gen_code_symbol_map(&self.root, &mut String::new(), &mut codes);
 
         codes
     }
 
-    fn rec_gen_char_code_map(
-        node: &HuffmanNode,
+    /// Bit-pack `input` using this tree's codes: walk each symbol's code
+    /// MSB-first into an accumulator byte, flushing it to the output
+    /// whenever 8 bits accumulate. The final partial byte, if any, is
+    /// padded with trailing zero bits, so the caller must track how many bits
+    /// were actually written (e.g. the sum of each symbol's code length) to
+    /// pass to [`HuffmanTree::decode`].
+    pub fn encode(&self, input: &[T]) -> Vec<u8> {
+        let codes = self.gen_symbol_code_map();
+
+        let mut output = Vec::new();
+        let mut acc: u8 = 0;
+        let mut acc_len = 0u8;
+
+        for symbol in input {
+            let bits = codes.get(symbol).expect("symbol has no code in this tree");
+            for bit in bits.chars() {
+                acc = (acc << 1) | if bit == '1' { 1 } else { 0 };
+                acc_len += 1;
+
+                if acc_len == 8 {
+                    output.push(acc);
+                    acc = 0;
+                    acc_len = 0;
+                }
+            }
+        }
+
+        if acc_len > 0 {
+            acc <<= 8 - acc_len;
+            output.push(acc);
+        }
+
+        output
+    }
+
+    /// Unpack `bytes` back into symbols by walking this tree one bit at a
+    /// time from the root: `0` goes left, `1` goes right, and reaching a
+    /// leaf emits its symbol and resets back to the root. Stops after
+    /// `bit_len` bits, ignoring any zero padding in the final byte.
+    pub fn decode(&self, bytes: &[u8], bit_len: usize) -> Vec<T> {
+        let mut result = Vec::new();
+        let mut node = &self.root;
+        let mut bits_read = 0;
+
+        'bytes: for byte in bytes {
+            for i in (0..8).rev() {
+                if bits_read == bit_len {
+                    break 'bytes;
+                }
+
+                let bit = (byte >> i) & 1;
+                node = match node {
+                    HuffmanNode::Internal { left, right, .. } => {
+                        if bit == 0 {
+                            left.as_ref()
+                        } else {
+                            right.as_ref()
+                        }
+                    }
+                    HuffmanNode::Leaf { .. } => unreachable!("walked past a leaf"),
+                };
+                bits_read += 1;
+
+                if let HuffmanNode::Leaf { element, .. } = node {
+                    result.push(element.clone());
+                    node = &self.root;
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Compile a jump-table decoder that consumes `k` bits per step instead
+    /// of walking the tree one bit at a time. Each table has `2^k` entries,
+    /// one per possible `k`-bit combination starting from that table's tree
+    /// node: an entry is [`Decode::Done`] if a leaf is reached within the
+    /// `k` bits (recording how many of them were actually part of the
+    /// code), or [`Decode::Continue`] pointing at the table for whichever
+    /// internal node the walk stopped at.
+    pub fn compile_decoder(&self, k: u8) -> CompiledDecoder<T> {
+        let mut nodes: Vec<&HuffmanNode<T>> = vec![&self.root];
+        let mut tables: Vec<Vec<Decode<T>>> = Vec::new();
+
+        let mut table_index = 0;
+        while table_index < nodes.len() {
+            let start = nodes[table_index];
+            let size = 1usize << k;
+            let mut table = Vec::with_capacity(size);
+
+            for combo in 0..size {
+                let mut node = start;
+                let mut consumed = 0u8;
+                let mut done = None;
+
+                while consumed < k {
+                    match node {
+                        HuffmanNode::Leaf { element, .. } => {
+                            done = Some(Decode::Done {
+                                symbol: element.clone(),
+                                bits_consumed: consumed,
+                            });
+                            break;
+                        }
+                        HuffmanNode::Internal { left, right, .. } => {
+                            let bit = (combo >> (k - 1 - consumed)) & 1;
+                            node = if bit == 0 { left.as_ref() } else { right.as_ref() };
+                            consumed += 1;
+                        }
+                    }
+                }
+
+                table.push(done.unwrap_or_else(|| match node {
+                    HuffmanNode::Leaf { element, .. } => Decode::Done {
+                        symbol: element.clone(),
+                        bits_consumed: k,
+                    },
+                    HuffmanNode::Internal { .. } => {
+                        let next_table = nodes
+                            .iter()
+                            .position(|n| std::ptr::eq(*n, node))
+                            .unwrap_or_else(|| {
+                                nodes.push(node);
+                                nodes.len() - 1
+                            });
+                        Decode::Continue { next_table }
+                    }
+                }));
+            }
+
+            tables.push(table);
+            table_index += 1;
+        }
+
+        CompiledDecoder { k, tables }
+    }
+
+    fn rec_gen_symbol_code_map(
+        node: &HuffmanNode<T>,
         prefix: &mut String,
-        code_table: &mut HashMap<char, String>,
+        code_table: &mut HashMap<T, String>,
     ) {
         match node {
             HuffmanNode::Leaf { element, .. } => {
-                code_table.insert(*element, prefix.clone());
+                code_table.insert(element.clone(), prefix.clone());
             }
             HuffmanNode::Internal { left, right, .. } => {
                 prefix.push('0');
-                HuffmanTree::rec_gen_char_code_map(left, prefix, code_table);
+                HuffmanTree::rec_gen_symbol_code_map(left, prefix, code_table);
                 prefix.pop();
 
                 prefix.push('1');
-                HuffmanTree::rec_gen_char_code_map(right, prefix, code_table);
+                HuffmanTree::rec_gen_symbol_code_map(right, prefix, code_table);
                 prefix.pop();
             }
         }
     }
 
-    fn rec_gen_code_char_map(
-        node: &HuffmanNode,
+    fn rec_gen_code_symbol_map(
+        node: &HuffmanNode<T>,
         prefix: &mut String,
-        code_table: &mut HashMap<String, char>,
+        code_table: &mut HashMap<String, T>,
     ) {
         match node {
             HuffmanNode::Leaf { element, .. } => {
-                code_table.insert(prefix.clone(), *element);
+                code_table.insert(prefix.clone(), element.clone());
             }
             HuffmanNode::Internal { left, right, .. } => {
                 prefix.push('0');
-                HuffmanTree::rec_gen_code_char_map(left, prefix, code_table);
+                HuffmanTree::rec_gen_code_symbol_map(left, prefix, code_table);
                 prefix.pop();
 
                 prefix.push('1');
-                HuffmanTree::rec_gen_code_char_map(right, prefix, code_table);
+                HuffmanTree::rec_gen_code_symbol_map(right, prefix, code_table);
                 prefix.pop();
             }
         }
     }
+
+    /// Compute the bit-length of each symbol's code, i.e. its depth in the
+    /// tree. This is all a canonical header needs to keep: both sides can
+    /// re-derive identical codes from just the lengths, via
+    /// [`HuffmanTree::canonical_codes`].
+    pub fn code_lengths(tree: &HuffmanTree<T>) -> HashMap<T, u8> {
+        let mut lengths = HashMap::new();
+        HuffmanTree::rec_code_lengths(&tree.root, 0, &mut lengths);
+        lengths
+    }
+
+    fn rec_code_lengths(node: &HuffmanNode<T>, depth: u8, lengths: &mut HashMap<T, u8>) {
+        match node {
+            HuffmanNode::Leaf { element, .. } => {
+                lengths.insert(element.clone(), depth);
+            }
+            HuffmanNode::Internal { left, right, .. } => {
+                HuffmanTree::rec_code_lengths(left, depth + 1, lengths);
+                HuffmanTree::rec_code_lengths(right, depth + 1, lengths);
+            }
+        }
+    }
+
+    /// Cap every code length at `max_len`, redistributing the deepest
+    /// leaves so the set of lengths still satisfies the Kraft inequality
+    /// (i.e. still describes a valid prefix code).
+    ///
+    /// This uses the classic DEFLATE length-limiting trick: push every
+    /// overflowing symbol down to `max_len`, then repeatedly borrow back
+    /// the codespace that move cost by moving one symbol from the deepest
+    /// non-empty length below `max_len` one level deeper.
+    pub fn limit_code_lengths(lengths: &mut HashMap<T, u8>, max_len: u8) {
+        let longest = match lengths.values().max() {
+            Some(l) if *l > max_len => *l,
+            _ => return,
+        };
+
+        let mut count = vec![0u32; longest as usize + 1];
+        for len in lengths.values() {
+            count[*len as usize] += 1;
+        }
+
+        let mut overflow = 0u32;
+        for len in (max_len as usize + 1)..=longest as usize {
+            overflow += count[len];
+            count[len] = 0;
+        }
+        count[max_len as usize] += overflow;
+
+        // Kraft sum in units of 2^-max_len: a prefix code needs it to be at
+        // most 2^max_len.
+        let capacity = 1u64 << max_len;
+        let mut kraft: u64 = (1..=max_len as usize)
+            .map(|len| (count[len] as u64) << (max_len as usize - len))
+            .sum();
+
+        while kraft > capacity {
+            let mut bits = max_len as usize - 1;
+            while count[bits] == 0 {
+                bits -= 1;
+            }
+            count[bits] -= 1;
+            count[bits + 1] += 2;
+            count[max_len as usize] -= 1;
+            // Splitting one `bits` slot in two and taking one leaf off
+            // `max_len` lowers the Kraft sum by exactly one unit.
+            kraft -= 1;
+        }
+
+        // Re-assign lengths: the symbols that were originally shortest (most
+        // frequent) keep the shortest lengths, consumed from `count` in
+        // ascending order.
+        let mut symbols: Vec<(T, u8)> = lengths.iter().map(|(c, l)| (c.clone(), *l)).collect();
+        symbols.sort_by_key(|(c, l)| (*l, c.clone()));
+
+        let mut new_len = 1usize;
+        for (symbol, _) in symbols {
+            while new_len <= max_len as usize && count[new_len] == 0 {
+                new_len += 1;
+            }
+            count[new_len] -= 1;
+            lengths.insert(symbol, new_len as u8);
+        }
+    }
+
+    /// This tree's canonical codes as `(code, length)` pairs: the compact
+    /// representation a header only needs to store (see
+    /// [`HuffmanTree::from_code_lengths`]), since both sides can re-derive
+    /// identical codes from the lengths alone.
+    #[allow(dead_code)]
+    pub fn canonical_codes(&self) -> HashMap<T, (u32, u8)> {
+        assign_canonical_codes(&HuffmanTree::code_lengths(self))
+    }
+
+    /// Reconstruct a `HuffmanTree` from nothing but a canonical code-length
+    /// table, e.g. one just read back from a compressed file's header.
+    /// Re-derives the same canonical codes [`HuffmanTree::canonical_codes`]
+    /// would, then places each symbol's leaf exactly where its code's bit
+    /// path points, so [`HuffmanTree::decode`] on the result reproduces
+    /// exactly the codes the encoder used — without ever seeing the
+    /// original frequencies.
+    pub fn from_code_lengths(lengths: &HashMap<T, u8>) -> HuffmanTree<T> {
+        if lengths.len() == 1 {
+            let element = lengths.keys().next().unwrap().clone();
+            return HuffmanTree {
+                root: single_symbol_node(element),
+            };
+        }
+
+        let mut symbols: Vec<(T, u32, u8)> = assign_canonical_codes(lengths)
+            .into_iter()
+            .map(|(symbol, (code, len))| (symbol, code, len))
+            .collect();
+        symbols.sort_by_key(|(_, code, len)| (*len, *code));
+
+        HuffmanTree {
+            root: build_from_codes(&symbols, 0),
+        }
+    }
+}
+
+impl HuffmanTree<u8> {
+    /// Serialize this tree's canonical code-length table to `output`: both
+    /// sides can independently re-derive identical canonical codes from the
+    /// lengths alone (see [`HuffmanTree::canonical_codes`]), so that's all a
+    /// compressed file's header needs to store.
+    ///
+    /// Format:
+    /// - 4 bytes (LE `u32`) : length in bytes of the table that follows
+    /// - for each symbol    : 1 byte value, 1 byte code length
+    pub fn write_to<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        let mut entries: Vec<(u8, u8)> = HuffmanTree::code_lengths(self).into_iter().collect();
+        entries.sort_by_key(|(byte, _)| *byte);
+
+        let mut table = Vec::with_capacity(entries.len() * 2);
+        for (byte, len) in entries {
+            table.push(byte);
+            table.push(len);
+        }
+
+        output.write_all(&(table.len() as u32).to_le_bytes())?;
+        output.write_all(&table)
+    }
+
+    /// Read back a tree written by [`HuffmanTree::write_to`].
+    pub fn read_from<R: Read>(input: &mut R) -> io::Result<HuffmanTree<u8>> {
+        let mut table_len = [0u8; 4];
+        input.read_exact(&mut table_len)?;
+        let table_len = u32::from_le_bytes(table_len) as usize;
+
+        let mut table = vec![0u8; table_len];
+        input.read_exact(&mut table)?;
+
+        let mut lengths = HashMap::new();
+        let mut chunks = table.chunks_exact(2);
+        for entry in &mut chunks {
+            lengths.insert(entry[0], entry[1]);
+        }
+        if !chunks.remainder().is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated huffman header",
+            ));
+        }
+        if lengths.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "huffman header has no symbols",
+            ));
+        }
+
+        Ok(HuffmanTree::from_code_lengths(&lengths))
+    }
+}
+
+/// Deterministically assign canonical codes from a table of code lengths:
+/// symbols are ordered by `(code_length, symbol)`, the first one gets
+/// `code = 0`, and `code` is incremented after each symbol and left-shifted
+/// whenever the next symbol's length increases. This lets a decoder
+/// re-derive the exact same codes from the lengths alone.
+fn assign_canonical_codes<T: Clone + Ord + Hash>(lengths: &HashMap<T, u8>) -> HashMap<T, (u32, u8)> {
+    let mut symbols: Vec<(T, u8)> = lengths.iter().map(|(c, l)| (c.clone(), *l)).collect();
+    symbols.sort_by_key(|(c, l)| (*l, c.clone()));
+
+    let mut codes = HashMap::new();
+    let mut code: u32 = 0;
+    let mut prev_len = symbols.first().map_or(0, |(_, l)| *l);
+
+    for (symbol, len) in symbols {
+        code <<= len - prev_len;
+        codes.insert(symbol, (code, len));
+        code += 1;
+        prev_len = len;
+    }
+
+    codes
+}
+
+/// A single distinct symbol can't form a real binary tree (there's nothing
+/// to pair it with), but it still needs a 1-bit code to be encodable: give
+/// it two leaves, one on each branch, holding the same symbol. Which branch
+/// is taken when decoding is irrelevant since both lead to the same symbol.
+fn single_symbol_node<T: Clone>(element: T) -> HuffmanNode<T> {
+    HuffmanNode::Internal {
+        weight: 0,
+        left: Box::new(HuffmanNode::newaf(element.clone(), 0)),
+        right: Box::new(HuffmanNode::newaf(element, 0)),
+    }
+}
+
+/// Build a tree of leaves by partitioning `symbols` (sorted by code) on the
+/// bit at `depth` of each symbol's canonical code, recursing until each
+/// symbol's code is fully consumed.
+fn build_from_codes<T: Clone>(symbols: &[(T, u32, u8)], depth: u8) -> HuffmanNode<T> {
+    if let [(element, _, len)] = symbols {
+        if *len == depth {
+            return HuffmanNode::Leaf {
+                element: element.clone(),
+                weight: 0,
+            };
+        }
+    }
+
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    for (element, code, len) in symbols {
+        if (code >> (len - depth - 1)) & 1 == 0 {
+            left.push((element.clone(), *code, *len));
+        } else {
+            right.push((element.clone(), *code, *len));
+        }
+    }
+
+    HuffmanNode::Internal {
+        weight: 0,
+        left: Box::new(build_from_codes(&left, depth + 1)),
+        right: Box::new(build_from_codes(&right, depth + 1)),
+    }
 }
 
 #[cfg(test)]
@@ -184,14 +645,10 @@ mod tests {
     use super::HuffmanTree;
     use std::collections::HashMap;
 
-    #[test]
-    fn test_one_node() {
-        let mut hashmap = HashMap::new();
-        hashmap.insert('A', 70);
-
-        let tree = HuffmanTree::build_huffman(hashmap);
-
-        assert!(tree.is_none());
+    /// Render a canonical `(code, length)` pair as a "0"/"1" bit string, for
+    /// assertions that are easier to read and sort as text.
+    fn bitstring(code: u32, len: u8) -> String {
+        format!("{:0width$b}", code, width = len as usize)
     }
 
     #[test]
@@ -228,7 +685,7 @@ mod tests {
     }
 
     #[test]
-    fn test_char_code() {
+    fn test_symbol_code() {
         let mut hashmap = HashMap::new();
         hashmap.insert('C', 32);
         hashmap.insert('D', 42);
@@ -244,8 +701,8 @@ mod tests {
 
         println!("{:?}", tree);
 
-        let map = HuffmanTree::gen_char_code_map(tree);
-        let map2 = HuffmanTree::gen_code_char_map(tree2);
+        let map = tree.gen_symbol_code_map();
+        let map2 = tree2.gen_code_symbol_map();
 
         println!("{:?}", map.get(&'C').unwrap());
         assert!(*map2.get(map.get(&'C').unwrap()).unwrap() == 'C');
@@ -257,4 +714,225 @@ mod tests {
         assert!(*map2.get(map.get(&'U').unwrap()).unwrap() == 'U');
         assert!(*map2.get(map.get(&'Z').unwrap()).unwrap() == 'Z');
     }
+
+    #[test]
+    fn test_canonical_codes_are_prefix_free_and_roundtrip() {
+        let mut hashmap = HashMap::new();
+        hashmap.insert('C', 32);
+        hashmap.insert('D', 42);
+        hashmap.insert('E', 120);
+        hashmap.insert('K', 7);
+        hashmap.insert('L', 42);
+        hashmap.insert('M', 24);
+        hashmap.insert('U', 37);
+        hashmap.insert('Z', 2);
+
+        let tree = HuffmanTree::build_huffman(hashmap).unwrap();
+        let lengths = HuffmanTree::code_lengths(&tree);
+        let codes: HashMap<char, String> = tree
+            .canonical_codes()
+            .into_iter()
+            .map(|(symbol, (code, len))| (symbol, bitstring(code, len)))
+            .collect();
+
+        assert_eq!(codes.len(), lengths.len());
+        for (symbol, len) in &lengths {
+            assert_eq!(codes.get(symbol).unwrap().len(), *len as usize);
+        }
+
+        let mut sorted_codes: Vec<&String> = codes.values().collect();
+        sorted_codes.sort();
+        for pair in sorted_codes.windows(2) {
+            assert!(!pair[1].starts_with(pair[0].as_str()));
+        }
+    }
+
+    #[test]
+    fn test_limit_code_lengths_caps_and_stays_valid() {
+        // A heavily skewed distribution produces unbounded-length codes
+        // (one symbol per power of two) that must be limited to fit in a
+        // single header byte.
+        let mut hashmap = HashMap::new();
+        let mut weight = 1u32;
+        for c in 'a'..='q' {
+            hashmap.insert(c, weight);
+            weight *= 2;
+        }
+
+        let tree = HuffmanTree::build_huffman(hashmap).unwrap();
+        let mut lengths = HuffmanTree::code_lengths(&tree);
+        assert!(lengths.values().any(|l| *l > 8));
+
+        HuffmanTree::limit_code_lengths(&mut lengths, 8);
+        assert!(lengths.values().all(|l| *l <= 8));
+
+        // The resulting lengths must still satisfy the Kraft inequality,
+        // i.e. describe a valid prefix code.
+        let kraft: f64 = lengths.values().map(|l| 2f64.powi(-(*l as i32))).sum();
+        assert!(kraft <= 1.0 + 1e-9);
+
+        let codes: HashMap<char, String> = HuffmanTree::from_code_lengths(&lengths)
+            .canonical_codes()
+            .into_iter()
+            .map(|(symbol, (code, len))| (symbol, bitstring(code, len)))
+            .collect();
+        let mut sorted_codes: Vec<&String> = codes.values().collect();
+        sorted_codes.sort();
+        for pair in sorted_codes.windows(2) {
+            assert!(!pair[1].starts_with(pair[0].as_str()));
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrips() {
+        let mut hashmap = HashMap::new();
+        hashmap.insert('a', 5);
+        hashmap.insert('b', 2);
+        hashmap.insert('c', 1);
+        hashmap.insert('d', 1);
+
+        let tree = HuffmanTree::build_huffman(hashmap).unwrap();
+        let codes = tree.gen_symbol_code_map();
+
+        let input: Vec<char> = "abracadabra".replace('r', "a").chars().collect();
+        let bit_len: usize = input.iter().map(|c| codes.get(c).unwrap().len()).sum();
+
+        let encoded = tree.encode(&input);
+        assert!(encoded.len() <= bit_len.div_ceil(8) + 1);
+
+        let decoded = tree.decode(&encoded, bit_len);
+        assert_eq!(decoded, input);
+    }
+
+    #[test]
+    fn test_decode_ignores_final_byte_padding() {
+        let mut hashmap = HashMap::new();
+        hashmap.insert('a', 1);
+        hashmap.insert('b', 1);
+
+        let tree = HuffmanTree::build_huffman(hashmap).unwrap();
+        let input: Vec<char> = "aab".chars().collect();
+        let encoded = tree.encode(&input);
+
+        // "aab" needs 3 bits; the rest of the last byte is zero padding and
+        // must not be mistaken for extra symbols.
+        assert_eq!(tree.decode(&encoded, 3), input);
+    }
+
+    #[test]
+    fn test_from_code_lengths_reconstructs_a_tree_with_the_same_codes() {
+        let mut hashmap = HashMap::new();
+        hashmap.insert('C', 32);
+        hashmap.insert('D', 42);
+        hashmap.insert('E', 120);
+        hashmap.insert('K', 7);
+        hashmap.insert('L', 42);
+        hashmap.insert('M', 24);
+        hashmap.insert('U', 37);
+        hashmap.insert('Z', 2);
+
+        let tree = HuffmanTree::build_huffman(hashmap).unwrap();
+        let lengths = HuffmanTree::code_lengths(&tree);
+        let codes = tree.canonical_codes();
+
+        let rebuilt = HuffmanTree::from_code_lengths(&lengths);
+        assert_eq!(rebuilt.canonical_codes(), codes);
+
+        let input: Vec<char> = "CDEKLMUZ".chars().collect();
+        let bit_len: usize = input.iter().map(|c| codes.get(c).unwrap().1 as usize).sum();
+        let encoded = rebuilt.encode(&input);
+        assert_eq!(rebuilt.decode(&encoded, bit_len), input);
+    }
+
+    #[test]
+    fn test_compiled_decoder_matches_naive_decode() {
+        let mut hashmap = HashMap::new();
+        hashmap.insert('C', 32);
+        hashmap.insert('D', 42);
+        hashmap.insert('E', 120);
+        hashmap.insert('K', 7);
+        hashmap.insert('L', 42);
+        hashmap.insert('M', 24);
+        hashmap.insert('U', 37);
+        hashmap.insert('Z', 2);
+
+        let tree = HuffmanTree::build_huffman(hashmap).unwrap();
+        let codes = tree.gen_symbol_code_map();
+
+        let input: Vec<char> = "CUEZDKLMUCUEZKLEEDM".chars().collect();
+        let bit_len: usize = input.iter().map(|c| codes.get(c).unwrap().len()).sum();
+        let encoded = tree.encode(&input);
+
+        for k in 1..=4u8 {
+            let compiled = tree.compile_decoder(k);
+            assert_eq!(
+                compiled.decode_fast(&encoded, bit_len),
+                tree.decode(&encoded, bit_len),
+                "k={k} should match the naive decoder"
+            );
+        }
+    }
+
+    #[test]
+    fn test_compiled_decoder_handles_a_single_symbol_of_input() {
+        let mut hashmap = HashMap::new();
+        hashmap.insert('a', 1);
+        hashmap.insert('b', 1);
+
+        let tree = HuffmanTree::build_huffman(hashmap).unwrap();
+        let codes = tree.gen_symbol_code_map();
+
+        let bit_len = codes.get(&'a').unwrap().len();
+        let encoded = tree.encode(&['a']);
+
+        let compiled = tree.compile_decoder(3);
+        assert_eq!(compiled.decode_fast(&encoded, bit_len), vec!['a']);
+    }
+
+    #[test]
+    fn test_build_huffman_with_a_single_distinct_symbol_gets_a_one_bit_code() {
+        let mut hashmap = HashMap::new();
+        hashmap.insert('a', 5);
+
+        let tree = HuffmanTree::build_huffman(hashmap).unwrap();
+        let lengths = HuffmanTree::code_lengths(&tree);
+        assert_eq!(lengths, HashMap::from([('a', 1)]));
+
+        let input: Vec<char> = "aaaaa".chars().collect();
+        let encoded = tree.encode(&input);
+        assert_eq!(tree.decode(&encoded, input.len()), input);
+    }
+
+    #[test]
+    fn test_from_code_lengths_rebuilds_a_single_symbol_tree() {
+        let lengths = HashMap::from([('z', 1)]);
+        let tree = HuffmanTree::from_code_lengths(&lengths);
+
+        let input: Vec<char> = "zzz".chars().collect();
+        let encoded = tree.encode(&input);
+        assert_eq!(tree.decode(&encoded, input.len()), input);
+    }
+
+    #[test]
+    fn test_write_to_read_from_roundtrips_the_header() {
+        let mut hashmap = HashMap::new();
+        hashmap.insert(b'C', 32);
+        hashmap.insert(b'D', 42);
+        hashmap.insert(b'E', 120);
+        hashmap.insert(b'Z', 2);
+
+        let tree = HuffmanTree::build_huffman(hashmap).unwrap();
+        let mut lengths = HuffmanTree::code_lengths(&tree);
+        HuffmanTree::limit_code_lengths(&mut lengths, super::MAX_CODE_LENGTH);
+        let canonical_tree = HuffmanTree::from_code_lengths(&lengths);
+
+        let mut buf = Vec::new();
+        canonical_tree.write_to(&mut buf).unwrap();
+
+        let read_back = HuffmanTree::read_from(&mut buf.as_slice()).unwrap();
+        assert_eq!(
+            HuffmanTree::code_lengths(&read_back),
+            HuffmanTree::code_lengths(&canonical_tree)
+        );
+    }
 }
@@ -1,11 +1,10 @@
 mod args;
+mod block;
 mod error;
 mod huffman;
 
-use core::str;
-use std::cmp::max;
 use std::collections::HashMap;
-use std::fs::{self, File, OpenOptions};
+use std::fs::OpenOptions;
 use std::io::Read;
 use std::io::Write;
 use std::process::exit;
@@ -14,13 +13,19 @@ use args::Args;
 use error::Error;
 use huffman::HuffmanTree;
 
-type FreqTable = HashMap<char, u32>;
+type FreqTable = HashMap<u8, u32>;
+type LengthTable = HashMap<u8, u8>;
+
+/// Chunk size used by [`write_decoded_file`]'s [`huffman::CompiledDecoder`]:
+/// each table lookup resolves up to 8 bits instead of one.
+const DECODE_CHUNK_BITS: u8 = 8;
 
 fn usage() {
     eprintln!("Usage: huffman [COMMAND] <filename>");
     eprintln!("COMMANDS : ");
     eprintln!("\t-c          : Compress the file <filename>. Default.");
     eprintln!("\t-d          : Decompress the file <filename>");
+    eprintln!("\t-b          : Compress in blocks, enabling random access via decompress_range");
     eprintln!("\t-o <output> : Place the result in the specified file. Default to a.out");
 }
 
@@ -34,37 +39,28 @@ fn main() {
         }
     };
 
+    match run(args) {
+        Ok(s) => {
+            println!("{}", s);
+            exit(0);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            exit(1);
+        }
+    }
+}
+
+/// Dispatch to `encode`/`decode` depending on `args.mode` — the single entry
+/// point tying the CLI's `Args` to the actual compress/uncompress pipeline.
+fn run(args: Args) -> Result<String, Error> {
     match args.mode {
-        args::Mode::Compress => match encode(args) {
-            Ok(s) => {
-                println!("{}", s);
-                exit(0);
-            }
-            Err(e) => {
-                eprintln!("Error: {}", e);
-                exit(1);
-            }
-        },
-        args::Mode::Decompress => match decode(args) {
-            Ok(s) => println!("{}", s),
-            Err(e) => {
-                eprintln!("Error: {}", e);
-                exit(1);
-            }
-        },
+        args::Mode::Compress => encode(args),
+        args::Mode::Uncompress => decode(args),
     }
 }
 
 fn encode(args: Args) -> Result<String, Error> {
-    let freqs = get_frequencies(&args)?;
-    let huffman_tree = if let Some(t) = huffman::HuffmanTree::build_huffman(freqs.clone()) {
-        t
-    } else {
-        return Err(Error::NotEnoughDifferentChars);
-    };
-
-    let codes = HuffmanTree::gen_char_code_map(huffman_tree);
-
     let mut output_file = OpenOptions::new()
         .truncate(true)
         .create(true)
@@ -72,262 +68,135 @@ fn encode(args: Args) -> Result<String, Error> {
         .open(args.output)
         .map_err(|_| Error::FileWriting)?;
 
-    let mut input_file = File::open(args.input).map_err(|_| Error::FileUnreadable)?;
-    write_header(&mut output_file, freqs)?;
-    write_encoded_file(codes, &mut input_file, &mut output_file)?;
-
-    Ok("Ok".to_string())
-}
-
-fn write_encoded_file(
-    codes: HashMap<char, String>,
-    input_file: &mut File,
-    output_file: &mut File,
-) -> Result<(), Error> {
-    let mut buf: Vec<u8> = Vec::new();
-    let mut bit_buffer = Vec::new();
-
-    let input_file = input_file;
-    let output_file = output_file;
-
-    let original_size = input_file
-        .metadata()
-        .map_err(|_| Error::FileUnreadable)?
-        .len();
+    if args.block {
+        let mut input_file = std::fs::File::open(args.input).map_err(|_| Error::FileUnreadable)?;
+        block::compress(&mut input_file, &mut output_file)?;
+        return Ok("Ok".to_string());
+    }
 
     output_file
-        .write(&original_size.to_le_bytes())
+        .write_all(&[block::FORMAT_SINGLE_STREAM])
         .map_err(|_| Error::FileWriting)?;
 
-    for_chars(input_file, |c| {
-        let bits = codes.get(&c).unwrap();
-        for bit in bits.chars() {
-            bit_buffer.push(if bit == '1' { 1 } else { 0 });
+    let mut freq_pass = std::fs::File::open(&args.input).map_err(|_| Error::FileUnreadable)?;
+    let (freqs, original_size) = get_frequencies(&mut freq_pass)?;
 
-            if bit_buffer.len() == 8 {
-                buf.push(bit_buffer.iter().fold(0, |acc, b| (acc << 1) | *b));
-                bit_buffer.clear();
-            }
+    let huffman_tree = huffman::HuffmanTree::build_huffman(freqs).ok_or(Error::EmptyFile)?;
 
-            if buf.len() >= 2048 {
-                let _ = output_file.write_all(&buf);
-                buf.clear();
-            }
-        }
-        Ok(())
-    })?;
+    let mut lengths = HuffmanTree::code_lengths(&huffman_tree);
+    HuffmanTree::limit_code_lengths(&mut lengths, huffman::MAX_CODE_LENGTH);
+    let canonical_tree = HuffmanTree::from_code_lengths(&lengths);
 
-    if bit_buffer.len() > 0 {
-        let mut last_byte = bit_buffer.iter().fold(0, |acc, b| (acc << 1) | *b);
-        last_byte = last_byte << 8 - bit_buffer.len();
-        buf.push(last_byte);
-        bit_buffer.clear();
-    }
+    let mut input_file = std::fs::File::open(args.input).map_err(|_| Error::FileUnreadable)?;
+    canonical_tree
+        .write_to(&mut output_file)
+        .map_err(|_| Error::FileWriting)?;
+    write_encoded_file(&canonical_tree, &mut input_file, &mut output_file, original_size)?;
 
-    if buf.len() > 0 {
-        output_file
-            .write_all(&buf)
-            .map_err(|_| Error::FileWriting)?;
-        buf.clear();
-    }
+    Ok("Ok".to_string())
+}
 
-    Ok(())
+/// Huffman-encode `input` into `output` using `tree`'s bit-packed
+/// [`HuffmanTree::encode`], prefixed with `original_size` (the number of
+/// bytes `input` is known to hold — the caller already learned this while
+/// computing frequencies, since an arbitrary `Read` may not support
+/// seeking back to ask its size).
+fn write_encoded_file<R: Read, W: Write>(
+    tree: &HuffmanTree<u8>,
+    input: &mut R,
+    output: &mut W,
+    original_size: u64,
+) -> Result<(), Error> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data).map_err(|_| Error::FileReading)?;
+
+    output
+        .write_all(&original_size.to_le_bytes())
+        .map_err(|_| Error::FileWriting)?;
+    output
+        .write_all(&tree.encode(&data))
+        .map_err(|_| Error::FileWriting)
 }
 
 fn decode(args: Args) -> Result<String, Error> {
-    let mut file = fs::File::open(args.input).map_err(|_| Error::FileUnreadable)?;
-    let mut output_file = fs::OpenOptions::new()
+    let mut file = std::fs::File::open(args.input).map_err(|_| Error::FileUnreadable)?;
+    let mut output_file = OpenOptions::new()
         .write(true)
         .truncate(true)
         .create(true)
         .open(args.output)
         .map_err(|_| Error::InvalidFile)?;
 
-    let table = read_header(&mut file)?;
-
-    let huffman_tree = if let Some(t) = huffman::HuffmanTree::build_huffman(table) {
-        t
-    } else {
-        return Err(Error::NotEnoughDifferentChars);
-    };
+    if block::is_block_mode(&mut file)? {
+        return block::decompress_range(&mut file, &mut output_file, 0, u64::MAX)
+            .map(|_| "".to_string());
+    }
 
-    let codes = HuffmanTree::gen_code_char_map(huffman_tree);
-    write_decoded_file(codes, &mut file, &mut output_file)?;
+    let canonical_tree = HuffmanTree::read_from(&mut file).map_err(|_| Error::InvalidFile)?;
+    write_decoded_file(&canonical_tree, &mut file, &mut output_file)?;
 
     Ok("".to_string())
 }
 
-fn write_decoded_file(
-    codes: HashMap<String, char>,
-    input_file: &mut File,
-    output_file: &mut File,
+/// Undo [`write_encoded_file`]: read back the original byte count, compile
+/// a table-driven decoder for `tree`, and decode every remaining byte of
+/// `input`. Decoding runs a few bits past the real content into the final
+/// byte's zero padding, so the result is truncated to the byte count that
+/// was actually written.
+fn write_decoded_file<R: Read, W: Write>(
+    tree: &HuffmanTree<u8>,
+    input: &mut R,
+    output: &mut W,
 ) -> Result<(), Error> {
-    let file = input_file;
-    let output_file = output_file;
-
     let mut nb_of_bytes = [0u8; 8];
-    file.read_exact(&mut nb_of_bytes)
+    input
+        .read_exact(&mut nb_of_bytes)
         .map_err(|_| Error::InvalidFile)?;
-    let nb_of_bytes = u64::from_le_bytes(nb_of_bytes);
-
-    let max_len_code = codes.iter().fold(0, |acc, e| max(acc, e.0.len()));
+    let nb_of_bytes = u64::from_le_bytes(nb_of_bytes) as usize;
 
-    let mut buf = [0; 2048];
-    let mut current_prefix = "".to_string();
-    let mut decoded_chars = Vec::new();
-    let mut bytes_decoded = 0;
-    while let Ok(n) = file.read(&mut buf) {
-        if n == 0 {
-            break;
-        }
-        for i in 0..n {
-            let byte = buf[i];
-            for j in (0..8).rev() {
-                current_prefix.push(if (byte >> j) & 1 == 1 { '1' } else { '0' });
-                if let Some(c) = codes.get(&current_prefix) {
-                    decoded_chars.push(*c);
-                    bytes_decoded += 1;
-                    current_prefix.clear();
-                    if bytes_decoded == nb_of_bytes {
-                        break;
-                    }
-                }
-            }
-            if current_prefix.len() > max_len_code {
-                return Err(Error::InvalidFile);
-            }
-        }
-
-        let out: String = decoded_chars.iter().map(|c| String::from(*c)).collect();
-        output_file
-            .write_all(out.as_bytes())
-            .map_err(|_| Error::FileWriting)?;
-        decoded_chars.clear();
-    }
-    Ok(())
-}
-
-/// Write the frequency table to the beginning of the file following this format :
-///
-/// - 4 bytes integer : indicating the nb of bytes for the rest of this header
-/// - for entries in table :
-///     - 1 byte integer  : length (n) of char
-///     - n bytes         : character
-///     - 4 bytes integer : frequency
-fn write_header(file: &mut File, freqs: FreqTable) -> Result<(), Error> {
-    let mut freq_bytes: Vec<u8> = Vec::new();
-    for (c, f) in freqs {
-        let mut buf = [0; 4];
-        let encoded_char = c.encode_utf8(&mut buf);
-        freq_bytes.extend((encoded_char.len() as u8).to_le_bytes());
-        freq_bytes.extend_from_slice(encoded_char.as_bytes());
-        freq_bytes.extend(f.to_le_bytes());
-    }
-
-    let output_file = file;
-
-    output_file
-        .write_all(&(freq_bytes.len() as u32).to_le_bytes())
-        .map_err(|_| Error::FileWriting)?;
-
-    output_file
-        .write_all(&freq_bytes)
-        .map_err(|_| Error::FileWriting)?;
-
-    Ok(())
-}
-
-fn read_header(file: &mut File) -> Result<FreqTable, Error> {
-    let file = file;
-
-    let mut header_size_len = [0u8; 4];
-    file.read_exact(&mut header_size_len)
+    let mut encoded = Vec::new();
+    input
+        .read_to_end(&mut encoded)
         .map_err(|_| Error::FileReading)?;
-    let header_size_len = u32::from_le_bytes(header_size_len);
-
-    let mut header = vec![0u8; header_size_len as usize];
-    file.read_exact(&mut header)
-        .map_err(|_| Error::FileReading)?;
-
-    let mut table = FreqTable::new();
 
-    let mut iter = header.iter();
-    while let Some(b) = iter.next() {
-        let char_size = u8::from_le_bytes([*b]);
-        let mut char_buf = vec![0; char_size as usize];
-        for i in 0..char_size {
-            char_buf[i as usize] = match iter.next() {
-                Some(b) => *b,
-                None => return Err(Error::InvalidFile),
-            }
-        }
-        let char = match str::from_utf8(&char_buf)
-            .map_err(|_| Error::InvalidFile)?
-            .chars()
-            .nth(0)
-        {
-            Some(c) => c,
-            None => return Err(Error::InvalidFile),
-        };
-
-        let mut f_buf = [0; 4];
-        for i in 0..4 {
-            f_buf[i] = match iter.next() {
-                Some(b) => *b,
-                None => return Err(Error::InvalidFile),
-            }
-        }
-        let freq = u32::from_le_bytes(f_buf);
+    let decoder = tree.compile_decoder(DECODE_CHUNK_BITS);
+    let mut decoded = decoder.decode_fast(&encoded, encoded.len() * 8);
+    decoded.truncate(nb_of_bytes);
 
-        table.insert(char, freq);
-    }
-    Ok(table)
+    output.write_all(&decoded).map_err(|_| Error::FileWriting)
 }
 
-fn get_frequencies(args: &Args) -> Result<FreqTable, Error> {
+/// Compute the frequency of every byte read from `input`, along with the
+/// total number of bytes read. The byte count stands in for the
+/// `original_size` the caller used to get from `File::metadata`, which
+/// isn't available on an arbitrary `Read`.
+fn get_frequencies<R: Read>(input: &mut R) -> Result<(FreqTable, u64), Error> {
     let mut frequencies: FreqTable = HashMap::new();
+    let mut byte_count = 0u64;
 
-    let mut file = File::open(args.input.clone()).map_err(|_| Error::FileUnreadable)?;
-    for_chars(&mut file, |c| {
-        *frequencies.entry(c).or_insert(0) += 1;
+    for_bytes(input, |byte| {
+        *frequencies.entry(byte).or_insert(0) += 1;
+        byte_count += 1;
         Ok(())
     })?;
 
-    Ok(frequencies)
+    Ok((frequencies, byte_count))
 }
 
-fn for_chars<F>(file: &mut File, mut f: F) -> Result<(), Error>
+fn for_bytes<R: Read, F>(input: &mut R, mut f: F) -> Result<(), Error>
 where
-    F: FnMut(char) -> Result<(), Error>,
+    F: FnMut(u8) -> Result<(), Error>,
 {
-    let file = file;
     let mut buf = [0; 2048];
-    let mut left_overs: Vec<u8> = Vec::new();
 
-    while let Ok(amount_read) = file.read(&mut buf) {
+    loop {
+        let amount_read = input.read(&mut buf).map_err(|_| Error::FileReading)?;
         if amount_read == 0 {
             break;
         }
 
-        let mut chunk = left_overs.clone();
-        chunk.extend_from_slice(&buf[..amount_read]);
-
-        match std::str::from_utf8(&chunk) {
-            Ok(valid_str) => {
-                for c in valid_str.chars() {
-                    f(c)?;
-                }
-                left_overs.clear();
-            }
-            Err(e) => {
-                let valid_up_to = e.valid_up_to();
-                if valid_up_to > 0 {
-                    for c in std::str::from_utf8(&chunk[..valid_up_to]).unwrap().chars() {
-                        f(c)?;
-                    }
-                }
-                left_overs = chunk[valid_up_to..].to_vec();
-            }
+        for byte in &buf[..amount_read] {
+            f(*byte)?;
         }
     }
     Ok(())
@@ -336,39 +205,34 @@ where
 #[cfg(test)]
 mod tests {
     use std::{
-        collections::HashMap,
-        fs::{read_to_string, remove_file, File, OpenOptions},
+        fs::{read, remove_file, File, OpenOptions},
         io::{Read, Write},
     };
 
     use crate::{
-        args::Mode, decode, encode, for_chars, get_frequencies, huffman::HuffmanTree, read_header,
-        write_decoded_file, write_encoded_file, write_header, Args, FreqTable,
+        args::Mode, for_bytes, get_frequencies, huffman, huffman::HuffmanTree, run,
+        write_decoded_file, write_encoded_file, Args, FreqTable, LengthTable,
     };
 
     #[test]
     fn test_frequencies() {
-        let args = Args {
-            input: "test.txt".to_string(),
-            output: "a.out".to_string(),
-            mode: Mode::Compress,
-        };
-        let freq = get_frequencies(&args).unwrap();
+        let mut file = File::open("test.txt").unwrap();
+        let (freq, _) = get_frequencies(&mut file).unwrap();
 
-        assert_eq!(*freq.get(&'X').unwrap(), 333);
-        assert_eq!(*freq.get(&'t').unwrap(), 223000);
+        assert_eq!(*freq.get(&b'X').unwrap(), 333);
+        assert_eq!(*freq.get(&b't').unwrap(), 223000);
     }
 
     #[test]
-    fn test_for_chars() {
+    fn test_for_bytes() {
         let mut n = 0;
         let mut file = File::open("test.txt").unwrap();
-        for_chars(&mut file, |_| Ok(n += 1)).unwrap();
-        assert_eq!(n, 3324222);
+        for_bytes(&mut file, |_| Ok(n += 1)).unwrap();
+        assert_eq!(n, 342190);
     }
 
     #[test]
-    fn test_for_chars_2() {
+    fn test_for_bytes_2() {
         let mut file = File::open("test.txt").unwrap();
         let mut file_copy = OpenOptions::new()
             .truncate(true)
@@ -377,23 +241,13 @@ mod tests {
             .open("test_copy.test")
             .unwrap();
 
-        for_chars(&mut file, |c| {
-            let mut buf = [0u8; 4];
-            let s = c.encode_utf8(&mut buf);
-            Ok(file_copy.write(s.as_bytes()).map(|_| ()).unwrap())
+        for_bytes(&mut file, |b| {
+            Ok(file_copy.write(&[b]).map(|_| ()).unwrap())
         })
         .unwrap();
 
-        let mut s1 = String::new();
-        let mut s2 = String::new();
-        File::open("test.txt")
-            .unwrap()
-            .read_to_string(&mut s1)
-            .unwrap();
-        File::open("test_copy.test")
-            .unwrap()
-            .read_to_string(&mut s2)
-            .unwrap();
+        let s1 = read("test.txt").unwrap();
+        let s2 = read("test_copy.test").unwrap();
 
         assert_eq!(s1, s2);
 
@@ -402,8 +256,9 @@ mod tests {
 
     #[test]
     fn test_header() {
-        let mut freqs = FreqTable::new();
-        freqs.insert('a', 10);
+        let mut lengths = LengthTable::new();
+        lengths.insert(b'a', 1);
+        lengths.insert(b'b', 1);
 
         let path = "test_header.txt";
 
@@ -414,23 +269,26 @@ mod tests {
             .open(path)
             .unwrap();
 
-        write_header(&mut f, freqs.clone()).unwrap();
+        HuffmanTree::from_code_lengths(&lengths)
+            .write_to(&mut f)
+            .unwrap();
 
         let mut f = File::open(path).unwrap();
-        let freqs_read = read_header(&mut f).unwrap();
+        let tree_read = HuffmanTree::read_from(&mut f).unwrap();
+        let lengths_read = HuffmanTree::code_lengths(&tree_read);
 
-        assert_eq!(freqs.len(), freqs_read.len());
-        assert_eq!(freqs.get(&'a').unwrap(), freqs_read.get(&'a').unwrap());
+        assert_eq!(lengths, lengths_read);
 
         remove_file(path).unwrap();
     }
 
     #[test]
     fn test_header_2() {
-        let mut freqs = FreqTable::new();
-        freqs.insert('a', 10);
-        freqs.insert('\n', 100000);
-        freqs.insert('\u{feff}', 800000);
+        let mut lengths = LengthTable::new();
+        lengths.insert(b'a', 3);
+        lengths.insert(b'\n', 1);
+        lengths.insert(0xff, 2);
+        lengths.insert(b'b', 3);
 
         let path = "test_header2.txt";
 
@@ -441,18 +299,15 @@ mod tests {
             .open(path)
             .unwrap();
 
-        write_header(&mut f, freqs.clone()).unwrap();
+        HuffmanTree::from_code_lengths(&lengths)
+            .write_to(&mut f)
+            .unwrap();
 
         let mut f = File::open(path).unwrap();
-        let freqs_read = read_header(&mut f).unwrap();
+        let tree_read = HuffmanTree::read_from(&mut f).unwrap();
+        let lengths_read = HuffmanTree::code_lengths(&tree_read);
 
-        assert_eq!(freqs.len(), freqs_read.len());
-        assert_eq!(freqs.get(&'a').unwrap(), freqs_read.get(&'a').unwrap());
-        assert_eq!(freqs.get(&'\n').unwrap(), freqs_read.get(&'\n').unwrap());
-        assert_eq!(
-            freqs.get(&'\u{feff}').unwrap(),
-            freqs_read.get(&'\u{feff}').unwrap()
-        );
+        assert_eq!(lengths, lengths_read);
 
         remove_file(path).unwrap();
     }
@@ -476,14 +331,15 @@ mod tests {
                 .open(path2)
                 .unwrap();
 
-            let mut codes = HashMap::new();
-            codes.insert('a', "1".to_string());
-            codes.insert('\n', "0".to_string());
+            let mut lengths = LengthTable::new();
+            lengths.insert(b'a', 1);
+            lengths.insert(b'\n', 1);
+            let tree = HuffmanTree::from_code_lengths(&lengths);
 
             write!(file, "a\naaa").unwrap();
             file.flush().unwrap();
             let mut file = File::open(path).unwrap();
-            write_encoded_file(codes, &mut file, &mut file2).unwrap();
+            write_encoded_file(&tree, &mut file, &mut file2, 5).unwrap();
         }
         let mut f = File::open(path2).unwrap();
         let mut buf = [0; 9];
@@ -513,15 +369,16 @@ mod tests {
                 .open(path2)
                 .unwrap();
 
-            let mut codes = HashMap::new();
-            codes.insert("1".to_string(), 'a');
-            codes.insert("0".to_string(), '\n');
+            let mut lengths = LengthTable::new();
+            lengths.insert(b'a', 1);
+            lengths.insert(b'\n', 1);
+            let tree = HuffmanTree::from_code_lengths(&lengths);
 
             file.write(&5u64.to_le_bytes()).unwrap();
             file.write(&[184]).unwrap();
             file.flush().unwrap();
             let mut file = File::open(path).unwrap();
-            write_decoded_file(codes, &mut file, &mut file2).unwrap();
+            write_decoded_file(&tree, &mut file, &mut file2).unwrap();
         }
         let mut f = File::open(path2).unwrap();
         let mut buf = [0; 5];
@@ -533,9 +390,9 @@ mod tests {
     }
 
     #[test]
-    fn test_encode_non_ascii() {
-        let path = "test_encode_non_ascii.test";
-        let path2 = "test_encode2_non_ascii.test";
+    fn test_encode_multibyte_utf8() {
+        let path = "test_encode_multibyte.test";
+        let path2 = "test_encode2_multibyte.test";
         {
             let mut file = OpenOptions::new()
                 .create(true)
@@ -551,66 +408,92 @@ mod tests {
                 .open(path2)
                 .unwrap();
 
-            let mut codes = HashMap::new();
-            codes.insert('é', "1".to_string());
-            codes.insert('$', "0".to_string());
+            // "é$ééé" as raw UTF-8 bytes, to check that multi-byte
+            // characters are treated as plain bytes rather than specially.
+            let text = "é$ééé".as_bytes().to_vec();
+            let mut freqs: FreqTable = FreqTable::new();
+            for &b in &text {
+                *freqs.entry(b).or_insert(0) += 1;
+            }
+            let tree = HuffmanTree::build_huffman(freqs).unwrap();
+            let tree = HuffmanTree::from_code_lengths(&HuffmanTree::code_lengths(&tree));
 
-            write!(file, "é$ééé").unwrap();
+            file.write_all(&text).unwrap();
             file.flush().unwrap();
             let mut file = File::open(path).unwrap();
-            write_encoded_file(codes, &mut file, &mut file2).unwrap();
+            write_encoded_file(&tree, &mut file, &mut file2, text.len() as u64).unwrap();
         }
         let mut f = File::open(path2).unwrap();
-        let mut buf = [0; 9];
-        f.read_exact(&mut buf).unwrap();
+        let mut header = [0u8; 8];
+        f.read_exact(&mut header).unwrap();
+        assert_eq!(9, u64::from_le_bytes(header));
 
-        assert_eq!(184, buf[8]);
         remove_file(path).unwrap();
         remove_file(path2).unwrap();
     }
 
     #[test]
-    fn test_decode_non_ascii() {
-        let path = "test_decode_non_ascii.test";
-        let path2 = "test_decode2_non_ascii.test";
+    fn full_test() {
+        let in_path = "full.test";
+        let out_path = "full_recovered.test";
         {
             let mut file = OpenOptions::new()
                 .create(true)
                 .truncate(true)
                 .write(true)
-                .open(path)
+                .open(in_path)
                 .unwrap();
+            file.write("àéÔ%*$abcd1234([][][][][);".as_bytes()).unwrap();
+        }
 
-            let mut file2 = OpenOptions::new()
-                .create(true)
-                .truncate(true)
-                .write(true)
-                .open(path2)
-                .unwrap();
+        let mut in_file = File::open(in_path).unwrap();
+        let (freq1, _) = get_frequencies(&mut in_file).unwrap();
+
+        run(Args {
+            input: in_path.to_string(),
+            output: "temp.test".to_string(),
+            mode: Mode::Compress,
+            block: false,
+        })
+        .unwrap();
 
-            let mut codes = HashMap::new();
-            codes.insert("1".to_string(), 'é');
-            codes.insert("0".to_string(), '$');
+        run(Args {
+            input: "temp.test".to_string(),
+            output: out_path.to_string(),
+            mode: Mode::Uncompress,
+            block: false,
+        })
+        .unwrap();
 
-            file.write(&5u64.to_le_bytes()).unwrap();
-            file.write(&[184]).unwrap();
-            file.flush().unwrap();
-            let mut file = File::open(path).unwrap();
-            write_decoded_file(codes, &mut file, &mut file2).unwrap();
-        }
-        let mut f = File::open(path2).unwrap();
-        let mut buf = [0; 9];
-        f.read_exact(&mut buf).unwrap();
+        let s1 = read(in_path).unwrap();
+        let s2 = read(out_path).unwrap();
 
-        assert_eq!("é$ééé".as_bytes(), buf);
-        remove_file(path).unwrap();
-        remove_file(path2).unwrap();
+        let huff1 = HuffmanTree::build_huffman(freq1).unwrap();
+        let mut lengths1 = HuffmanTree::code_lengths(&huff1);
+        HuffmanTree::limit_code_lengths(&mut lengths1, huffman::MAX_CODE_LENGTH);
+
+        let mut f = File::open("temp.test").unwrap();
+        let mut flag = [0u8; 1];
+        f.read_exact(&mut flag).unwrap();
+        let tree2 = HuffmanTree::read_from(&mut f).unwrap();
+        let lengths2 = HuffmanTree::code_lengths(&tree2);
+
+        assert_eq!(lengths1, lengths2);
+        assert_eq!(s1, s2);
+
+        remove_file(in_path).unwrap();
+        remove_file(out_path).unwrap();
+        remove_file("temp.test").unwrap();
     }
 
+    /// A file with only one distinct byte can't form a real Huffman tree,
+    /// but `HuffmanTree::build_huffman` still gives it a 1-bit code instead
+    /// of failing, so compression should round-trip it like any other file.
     #[test]
-    fn full_test() {
-        let in_path = "full.test";
-        let out_path = "full_recovered.test";
+    fn test_roundtrip_single_byte_value_file() {
+        let in_path = "single_byte.test";
+        let out_path = "single_byte_recovered.test";
+        let compressed_path = "single_byte.huff";
         {
             let mut file = OpenOptions::new()
                 .create(true)
@@ -618,46 +501,85 @@ mod tests {
                 .write(true)
                 .open(in_path)
                 .unwrap();
-            file.write("àéÔ%*$abcd1234([][][][][);".as_bytes()).unwrap();
+            file.write_all(&[b'x'; 37]).unwrap();
         }
 
-        let args = Args {
+        run(Args {
             input: in_path.to_string(),
-            output: "temp.test".to_string(),
+            output: compressed_path.to_string(),
             mode: Mode::Compress,
-        };
-        let freq1 = get_frequencies(&args).unwrap();
-        encode(args).unwrap();
+            block: false,
+        })
+        .unwrap();
 
-        let args = Args {
-            input: "temp.test".to_string(),
+        run(Args {
+            input: compressed_path.to_string(),
             output: out_path.to_string(),
             mode: Mode::Uncompress,
-        };
-        decode(args).unwrap();
+            block: false,
+        })
+        .unwrap();
 
-        let s1 = read_to_string(in_path).unwrap();
-        let s2 = read_to_string(out_path).unwrap();
+        assert_eq!(read(in_path).unwrap(), read(out_path).unwrap());
 
-        let huff1 = HuffmanTree::build_huffman(freq1).unwrap();
+        remove_file(in_path).unwrap();
+        remove_file(out_path).unwrap();
+        remove_file(compressed_path).unwrap();
+    }
 
-        let mut f = File::open("temp.test").unwrap();
-        let freq2 = read_header(&mut f).unwrap();
-        let huff2 = HuffmanTree::build_huffman(freq2).unwrap();
-
-        let mut differents = Vec::new();
-        let codes = HuffmanTree::gen_char_code_map(huff2);
-        for (char, code) in HuffmanTree::gen_char_code_map(huff1) {
-            if *codes.get(&char).unwrap() != code {
-                differents.push(char);
+    /// A Fibonacci-weighted frequency distribution is the classic
+    /// worst case for Huffman tree depth: it naturally produces codes
+    /// longer than `MAX_CODE_LENGTH`, forcing `limit_code_lengths` to kick
+    /// in. Round-tripping this through the real `-c`/`-d` pipeline is a
+    /// regression test for a bug where that length-limiting step
+    /// corrupted the header and panicked instead of producing a valid file.
+    #[test]
+    fn test_roundtrip_forces_code_length_limiting() {
+        let in_path = "fib_skew.test";
+        let out_path = "fib_skew_recovered.test";
+        let compressed_path = "fib_skew.huff";
+        {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .truncate(true)
+                .write(true)
+                .open(in_path)
+                .unwrap();
+
+            // Give each of 24 distinct bytes a Fibonacci-scaled repeat
+            // count, so their natural Huffman code lengths run well past
+            // MAX_CODE_LENGTH (15).
+            let mut data = Vec::new();
+            let (mut a, mut b) = (1u32, 1u32);
+            for i in 0..24u8 {
+                data.extend(std::iter::repeat(i).take(a as usize));
+                let next = a + b;
+                a = b;
+                b = next;
             }
+            file.write_all(&data).unwrap();
         }
 
-        assert!(differents.len() == 0, "{:?}", differents);
-        assert_eq!(s1, s2);
+        run(Args {
+            input: in_path.to_string(),
+            output: compressed_path.to_string(),
+            mode: Mode::Compress,
+            block: false,
+        })
+        .unwrap();
+
+        run(Args {
+            input: compressed_path.to_string(),
+            output: out_path.to_string(),
+            mode: Mode::Uncompress,
+            block: false,
+        })
+        .unwrap();
+
+        assert_eq!(read(in_path).unwrap(), read(out_path).unwrap());
 
         remove_file(in_path).unwrap();
         remove_file(out_path).unwrap();
-        remove_file("temp.test").unwrap();
+        remove_file(compressed_path).unwrap();
     }
 }
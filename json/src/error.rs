@@ -1,43 +1,146 @@
-use std::fmt::Debug;
+use std::fmt::{self, Display};
 
 use crate::Token;
 
+/// Where in the source an error occurred: a 1-based line/column plus the
+/// full text of that line, so `Display` can draw a caret under the bad
+/// character the way rustc points at parse errors. `file` is filled in
+/// later, via [`Error::with_file`], once the caller printing the error
+/// knows what file the source came from — the parser itself only ever
+/// sees the source text.
+#[derive(Debug, Clone)]
+pub struct Pos {
+    pub line: usize,
+    pub col: usize,
+    pub source_line: String,
+    pub file: Option<String>,
+}
+
+impl Pos {
+    /// Locate the 1-based line/column of the `char_index`-th character of
+    /// `source`, along with the full text of the line it's on.
+    pub fn locate(source: &str, char_index: usize) -> Self {
+        let mut line = 1;
+        let mut col = 1;
+        for (i, c) in source.chars().enumerate() {
+            if i == char_index {
+                break;
+            }
+            if c == '\n' {
+                line += 1;
+                col = 1;
+            } else {
+                col += 1;
+            }
+        }
+        let source_line = source.lines().nth(line - 1).unwrap_or("").to_string();
+
+        Pos {
+            line,
+            col,
+            source_line,
+            file: None,
+        }
+    }
+}
+
+impl Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => writeln!(f, "  --> {file}:{}:{}", self.line, self.col)?,
+            None => writeln!(f, "  --> line {}, column {}", self.line, self.col)?,
+        }
+        writeln!(f, "   | {}", self.source_line)?;
+        write!(f, "   | {}^", " ".repeat(self.col.saturating_sub(1)))
+    }
+}
+
+#[derive(Debug)]
 pub enum Error {
-    SyntaxError(Token, u32), // Generic error, should be replaced with explicit ones
-    // The u32 refers to the line where it was created in the
-    // source code for debug purposes
-    UnrecognizedToken(char),
-    MustBeginWithBracket,
-    MissingClosingBracket,
-    MismatchQuote,
-    TrailingComma,
-    ParsingError,
-    InvalidNumber,
-    MissingValue,
-    ExtraValue,
+    SyntaxError(Token, Pos), // Generic error, should be replaced with explicit ones
+    UnrecognizedToken(char, Pos),
+    MustBeginWithBracket(Pos),
+    MissingClosingBracket(Pos),
+    MismatchQuote(Pos),
+    TrailingComma(Pos),
+    ParsingError(Pos),
+    InvalidNumber(Pos),
+    LeadingZero(Pos),
+    MissingFractionDigits(Pos),
+    MissingExponentDigits(Pos),
+    InvalidEscape(Pos),
+    LineBreakInLitteral(Pos),
+    MissingValue(Pos),
+    ExtraValue(Pos),
 }
 
-impl Debug for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Error {
+    /// Fill in the `file:line:col` prefix a top-level caller wants in the
+    /// error it prints, e.g. when `main.rs` reports a malformed input file
+    /// — the parser itself only ever has the source text, not the
+    /// filename it came from.
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.pos_mut().file = Some(file.into());
+        self
+    }
+
+    fn pos_mut(&mut self) -> &mut Pos {
         match self {
-            Error::UnrecognizedToken(c) => writeln!(f, "Error: {c} is an invalid token."),
-            Error::MustBeginWithBracket => {
-                writeln!(f, "Error: the json object must begin with '{{'.") // {{ to escape
+            Error::SyntaxError(_, p)
+            | Error::UnrecognizedToken(_, p)
+            | Error::MustBeginWithBracket(p)
+            | Error::MissingClosingBracket(p)
+            | Error::MismatchQuote(p)
+            | Error::TrailingComma(p)
+            | Error::ParsingError(p)
+            | Error::InvalidNumber(p)
+            | Error::LeadingZero(p)
+            | Error::MissingFractionDigits(p)
+            | Error::MissingExponentDigits(p)
+            | Error::InvalidEscape(p)
+            | Error::LineBreakInLitteral(p)
+            | Error::MissingValue(p)
+            | Error::ExtraValue(p) => p,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (message, pos) = match self {
+            Error::UnrecognizedToken(c, p) => (format!("{c} is an invalid token."), p),
+            Error::MustBeginWithBracket(p) => {
+                ("the json object must begin with '{'.".to_string(), p)
             }
-            Error::MissingClosingBracket => {
-                writeln!(f, "Error: a closing bracket '}}' is missing.")
+            Error::MissingClosingBracket(p) => ("a closing bracket '}' is missing.".to_string(), p),
+            Error::MismatchQuote(p) => ("a closing \" is missing.".to_string(), p),
+            Error::TrailingComma(p) => {
+                ("the object seems to have a trailing comma.".to_string(), p)
             }
-            Error::MismatchQuote => writeln!(f, "Error: a closing \" is missing."),
-            Error::TrailingComma => {
-                writeln!(f, "Error: the object seems to have a trailing comma.")
+            Error::InvalidNumber(p) => ("unable to parse number.".to_string(), p),
+            Error::LeadingZero(p) => {
+                ("numbers cannot have a leading zero.".to_string(), p)
             }
-            Error::InvalidNumber => writeln!(f, "Error: unable to parse number"),
-            Error::SyntaxError(tok, l) => {
-                writeln!(f, "Error: invalid syntax on token : {tok:?}. [l. {l}]")
+            Error::MissingFractionDigits(p) => {
+                ("expected at least one digit after the decimal point.".to_string(), p)
             }
-            Error::ParsingError => writeln!(f, "Error: parsing error."),
-            Error::MissingValue => writeln!(f, "Error: missing value after key definition."),
-            Error::ExtraValue => writeln!(f, "Error: extra token found after object"),
-        }
+            Error::MissingExponentDigits(p) => {
+                ("expected at least one digit in the exponent.".to_string(), p)
+            }
+            Error::InvalidEscape(p) => {
+                ("invalid or incomplete escape sequence in string.".to_string(), p)
+            }
+            Error::LineBreakInLitteral(p) => (
+                "a literal string contains an unescaped line break or tab.".to_string(),
+                p,
+            ),
+            Error::SyntaxError(tok, p) => (format!("invalid syntax on token: {tok:?}."), p),
+            Error::ParsingError(p) => ("parsing error.".to_string(), p),
+            Error::MissingValue(p) => ("missing value after key definition.".to_string(), p),
+            Error::ExtraValue(p) => ("extra token found after object.".to_string(), p),
+        };
+
+        writeln!(f, "Error: {message}")?;
+        write!(f, "{pos}")
     }
 }
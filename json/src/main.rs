@@ -1,4 +1,4 @@
-use crate::error::Error;
+use crate::error::{Error, Pos};
 use std::{env, process::exit};
 
 mod error;
@@ -18,6 +18,10 @@ enum Token {
     CloseList,
 }
 
+/// A token tagged with the char offset of its first character in the
+/// source, so parser errors can point back at exactly where they occurred.
+struct Spanned(Token, usize);
+
 #[derive(Debug, PartialEq)]
 enum Value {
     Null,
@@ -34,6 +38,7 @@ type Object = Vec<KV>;
 struct KV(String, Value);
 
 fn read_end_word(
+    source: &str,
     end_of_word: &str,
     iter: &mut dyn Iterator<Item = (usize, char)>,
 ) -> Result<(), Error> {
@@ -41,234 +46,601 @@ fn read_end_word(
         match (c, iter.next()) {
             (a, Some((i, b))) => {
                 if a != b {
-                    return Err(Error::UnrecognizedToken(b, i));
+                    return Err(Error::UnrecognizedToken(b, Pos::locate(source, i)));
                 }
             }
             _ => {
-                return Err(Error::ParsingError);
+                return Err(Error::ParsingError(Pos::locate(
+                    source,
+                    source.chars().count(),
+                )));
             }
         }
     }
     Ok(())
 }
 
-fn tokenize(input: String) -> Result<Vec<Token>, Error> {
+fn tokenize(input: &str) -> Result<Vec<Spanned>, Error> {
     let mut tokens = Vec::new();
     let mut iter = input.chars().enumerate();
+    let eof = || Pos::locate(input, input.chars().count());
     while let Some((i, ch)) = iter.next() {
         match ch {
-            '{' => tokens.push(Token::OpenBracket),
-            '}' => tokens.push(Token::CloseBracket),
-            '[' => tokens.push(Token::OpenList),
-            ']' => tokens.push(Token::CloseList),
-            ',' => tokens.push(Token::Comma),
-            ':' => tokens.push(Token::Colon),
+            '{' => tokens.push(Spanned(Token::OpenBracket, i)),
+            '}' => tokens.push(Spanned(Token::CloseBracket, i)),
+            '[' => tokens.push(Spanned(Token::OpenList, i)),
+            ']' => tokens.push(Spanned(Token::CloseList, i)),
+            ',' => tokens.push(Spanned(Token::Comma, i)),
+            ':' => tokens.push(Spanned(Token::Colon, i)),
             '"' => {
                 let mut l = String::new();
                 loop {
                     match iter.next() {
-                        Some((_, c)) => {
+                        Some((j, c)) => {
                             if c == '"' {
                                 break;
                             } else if c == '\\' {
-                                l.push(c);
                                 match iter.next() {
-                                    Some((_, c)) => l.push(c),
-                                    None => return Err(Error::MismatchQuote),
+                                    Some((k, e)) => read_escape(input, k, e, &mut iter, &mut l)?,
+                                    None => return Err(Error::MismatchQuote(eof())),
                                 }
+                            } else if c == '\n' || c == '\t' {
+                                return Err(Error::LineBreakInLitteral(Pos::locate(input, j)));
                             } else {
                                 l.push(c);
                             }
                         }
-                        None => return Err(Error::MismatchQuote),
+                        None => return Err(Error::MismatchQuote(eof())),
                     }
                 }
-                tokens.push(Token::Litteral(l))
+                tokens.push(Spanned(Token::Litteral(l), i))
             }
-            't' => match read_end_word("rue", &mut iter) {
-                Ok(()) => tokens.push(Token::True),
+            't' => match read_end_word(input, "rue", &mut iter) {
+                Ok(()) => tokens.push(Spanned(Token::True, i)),
                 Err(e) => return Err(e),
             },
-            'f' => match read_end_word("alse", &mut iter) {
-                Ok(()) => tokens.push(Token::False),
+            'f' => match read_end_word(input, "alse", &mut iter) {
+                Ok(()) => tokens.push(Spanned(Token::False, i)),
                 Err(e) => return Err(e),
             },
-            'n' => match read_end_word("ull", &mut iter) {
-                Ok(()) => tokens.push(Token::Null),
+            'n' => match read_end_word(input, "ull", &mut iter) {
+                Ok(()) => tokens.push(Spanned(Token::Null, i)),
                 Err(e) => return Err(e),
             },
             '\u{0020}' | '\u{000A}' | '\u{000D}' | '\u{0009}' => continue, // Ignore whitespaces, tabs, ...
-            c @ '-' | c @ '0'..='9' => match tokenize_digits(c, &mut iter) {
-                Ok(n) => tokens.push(Token::Number(n)),
+            c @ '-' | c @ '0'..='9' => match tokenize_digits(input, i, c, &mut iter) {
+                Ok(n) => tokens.push(Spanned(Token::Number(n), i)),
                 Err(e) => return Err(e),
             },
-            _ => return Err(Error::UnrecognizedToken(ch, i)),
+            _ => return Err(Error::UnrecognizedToken(ch, Pos::locate(input, i))),
         }
-        println!("{:?}", tokens[tokens.len() - 1]);
+        println!("{:?}", tokens[tokens.len() - 1].0);
     }
     Ok(tokens)
 }
 
+/// Decode a single escape sequence (the character right after a `\`) into
+/// its real character(s), pushing the result onto `out`. `\uXXXX` reads
+/// four more hex digits from `iter`; a high surrogate must be immediately
+/// followed by a `\uXXXX` low surrogate, which the two combine into a
+/// single supplementary-plane character.
+fn read_escape(
+    source: &str,
+    escape_pos: usize,
+    escaped: char,
+    iter: &mut dyn Iterator<Item = (usize, char)>,
+    out: &mut String,
+) -> Result<(), Error> {
+    match escaped {
+        '"' => out.push('"'),
+        '\\' => out.push('\\'),
+        '/' => out.push('/'),
+        'b' => out.push('\u{0008}'),
+        'f' => out.push('\u{000C}'),
+        'n' => out.push('\n'),
+        'r' => out.push('\r'),
+        't' => out.push('\t'),
+        'u' => {
+            let code_point = read_hex4(source, iter)?;
+            if (0xD800..=0xDBFF).contains(&code_point) {
+                match (iter.next(), iter.next()) {
+                    (Some((_, '\\')), Some((li, 'u'))) => {
+                        let low = read_hex4(source, iter)?;
+                        if !(0xDC00..=0xDFFF).contains(&low) {
+                            return Err(Error::InvalidEscape(Pos::locate(source, li)));
+                        }
+                        let combined = 0x10000 + ((code_point - 0xD800) << 10) + (low - 0xDC00);
+                        out.push(
+                            char::from_u32(combined)
+                                .ok_or_else(|| Error::InvalidEscape(Pos::locate(source, li)))?,
+                        );
+                    }
+                    _ => return Err(Error::InvalidEscape(Pos::locate(source, escape_pos))),
+                }
+            } else if (0xDC00..=0xDFFF).contains(&code_point) {
+                // A low surrogate must be preceded by a high surrogate, never on its own.
+                return Err(Error::InvalidEscape(Pos::locate(source, escape_pos)));
+            } else {
+                out.push(
+                    char::from_u32(code_point)
+                        .ok_or_else(|| Error::InvalidEscape(Pos::locate(source, escape_pos)))?,
+                );
+            }
+        }
+        _ => return Err(Error::InvalidEscape(Pos::locate(source, escape_pos))),
+    }
+    Ok(())
+}
+
+/// Read exactly 4 hex digits from `iter` as a `\uXXXX` code unit.
+fn read_hex4(source: &str, iter: &mut dyn Iterator<Item = (usize, char)>) -> Result<u32, Error> {
+    let mut digits = String::new();
+    let mut last_pos = source.chars().count();
+    for _ in 0..4 {
+        match iter.next() {
+            Some((i, c)) => {
+                last_pos = i;
+                digits.push(c);
+            }
+            None => return Err(Error::InvalidEscape(Pos::locate(source, last_pos))),
+        }
+    }
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::InvalidEscape(Pos::locate(source, last_pos)));
+    }
+    u32::from_str_radix(&digits, 16).map_err(|_| Error::InvalidEscape(Pos::locate(source, last_pos)))
+}
+
+/// Advance both `iter` (the shared tokenizer cursor) and `peekable` (our
+/// lookahead clone of it) by one character, appending it to `s`.
+fn consume(
+    iter: &mut std::iter::Enumerate<std::str::Chars<'_>>,
+    peekable: &mut std::iter::Peekable<std::iter::Enumerate<std::str::Chars<'_>>>,
+    s: &mut String,
+) {
+    if let Some((_, c)) = peekable.next() {
+        iter.next();
+        s.push(c);
+    }
+}
+
+/// Tokenize a JSON number, enforcing the spec's grammar instead of handing
+/// an arbitrary run of `[0-9Ee.+-]` to `f64::parse`: an optional leading
+/// `-`; an integer part that is either a lone `0` or `[1-9][0-9]*` (no
+/// leading zeros); an optional `.` fraction with at least one digit; an
+/// optional `e`/`E` exponent with an optional sign and at least one digit.
 fn tokenize_digits(
-    c: char,
+    source: &str,
+    start: usize,
+    first: char,
     iter: &mut std::iter::Enumerate<std::str::Chars<'_>>,
 ) -> Result<f64, Error> {
     let mut peekable = iter.clone().peekable();
     let mut s = String::new();
-    s.push(c);
+    s.push(first);
 
-    while let Some((_, ch)) = peekable.peek() {
-        if !"0123456789Ee.+-".contains(*ch) {
-            break;
+    let first_digit = if first == '-' {
+        match peekable.peek().copied() {
+            Some((_, d)) if d.is_ascii_digit() => {
+                consume(iter, &mut peekable, &mut s);
+                d
+            }
+            _ => return Err(Error::InvalidNumber(Pos::locate(source, start))),
+        }
+    } else {
+        first
+    };
+
+    if first_digit == '0' {
+        if let Some((p, d)) = peekable.peek().copied() {
+            if d.is_ascii_digit() {
+                return Err(Error::LeadingZero(Pos::locate(source, p)));
+            }
+        }
+    } else {
+        while let Some((_, d)) = peekable.peek().copied() {
+            if !d.is_ascii_digit() {
+                break;
+            }
+            consume(iter, &mut peekable, &mut s);
+        }
+    }
+
+    // Fraction part: a '.' must be followed by at least one digit.
+    if let Some((dot_pos, '.')) = peekable.peek().copied() {
+        consume(iter, &mut peekable, &mut s);
+        match peekable.peek().copied() {
+            Some((_, d)) if d.is_ascii_digit() => {}
+            _ => return Err(Error::MissingFractionDigits(Pos::locate(source, dot_pos))),
+        }
+        while let Some((_, d)) = peekable.peek().copied() {
+            if !d.is_ascii_digit() {
+                break;
+            }
+            consume(iter, &mut peekable, &mut s);
+        }
+    }
+
+    // Exponent part: 'e'/'E', an optional sign, then at least one digit.
+    if let Some((e_pos, e)) = peekable.peek().copied() {
+        if e == 'e' || e == 'E' {
+            consume(iter, &mut peekable, &mut s);
+            if let Some((_, sign)) = peekable.peek().copied() {
+                if sign == '+' || sign == '-' {
+                    consume(iter, &mut peekable, &mut s);
+                }
+            }
+            match peekable.peek().copied() {
+                Some((_, d)) if d.is_ascii_digit() => {}
+                _ => return Err(Error::MissingExponentDigits(Pos::locate(source, e_pos))),
+            }
+            while let Some((_, d)) = peekable.peek().copied() {
+                if !d.is_ascii_digit() {
+                    break;
+                }
+                consume(iter, &mut peekable, &mut s);
+            }
         }
-        peekable.next();
-        s.push(iter.next().unwrap().1)
     }
 
-    s.parse().map_err(|_| Error::InvalidNumber)
+    s.parse()
+        .map_err(|_| Error::InvalidNumber(Pos::locate(source, start)))
 }
 
-fn main() -> Result<(), Error> {
+fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Please provide a file");
-        exit(1);
+
+    let mut filename = None;
+    let mut pretty = false;
+    let mut query = None;
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => pretty = true,
+            "--minify" => pretty = false,
+            "--get" => query = iter.next().cloned(),
+            _ => filename = Some(arg.clone()),
+        }
+    }
+
+    let filename = match filename {
+        Some(f) => f,
+        None => {
+            eprintln!("Please provide a file");
+            exit(1);
+        }
+    };
+    let input = std::fs::read_to_string(&filename).expect("The provided file is unreadable.");
+
+    let object = match analyse(&input) {
+        Ok(object) => object,
+        Err(e) => {
+            eprintln!("{}", e.with_file(filename));
+            exit(1);
+        }
+    };
+    let opts = SerializeOptions {
+        pretty,
+        ..SerializeOptions::default()
+    };
+    let value = Value::Object(object);
+
+    match query {
+        Some(path) => match get(&value, &path) {
+            Some(selected) => println!("{}", serialize(selected, &opts)),
+            None => {
+                eprintln!("Error: no value found at path '{path}'");
+                exit(1);
+            }
+        },
+        None => println!("{}", serialize(&value, &opts)),
+    }
+}
+
+/// Options controlling how a `Value` is turned back into JSON text.
+pub struct SerializeOptions {
+    /// Insert newlines and indentation between object/array members.
+    pub pretty: bool,
+    /// Spaces per nesting level, only used when `pretty` is set.
+    pub indent_width: usize,
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        SerializeOptions {
+            pretty: false,
+            indent_width: 2,
+        }
+    }
+}
+
+/// Turn a parsed `Value` back into JSON text. Object key order is
+/// preserved (`Object` is a `Vec<KV>`, not a map), and a whole-number
+/// `Number` is printed without a trailing `.0` so integers round-trip as
+/// integers.
+fn serialize(value: &Value, opts: &SerializeOptions) -> String {
+    let mut out = String::new();
+    write_value(value, opts, 0, &mut out);
+    out
+}
+
+fn write_value(value: &Value, opts: &SerializeOptions, depth: usize, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&format_number(*n)),
+        Value::Str(s) => write_string(s, out),
+        Value::Array(items) => write_array(items, opts, depth, out),
+        Value::Object(kvs) => write_object(kvs, opts, depth, out),
+    }
+}
+
+/// Write a string value, re-escaping the characters the tokenizer decodes
+/// on the way in (quotes, backslashes, and control characters) so the
+/// output is valid JSON again.
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{0008}' => out.push_str("\\b"),
+            '\u{000C}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn format_number(n: f64) -> String {
+    if n.is_finite() && n.fract() == 0.0 && n.abs() < 1e15 {
+        format!("{}", n as i64)
+    } else {
+        format!("{}", n)
+    }
+}
+
+fn write_object(kvs: &Object, opts: &SerializeOptions, depth: usize, out: &mut String) {
+    if kvs.is_empty() {
+        out.push_str("{}");
+        return;
+    }
+
+    out.push('{');
+    for (i, KV(key, value)) in kvs.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        indent(opts, depth + 1, out);
+        out.push('"');
+        out.push_str(key);
+        out.push_str(if opts.pretty { "\": " } else { "\":" });
+        write_value(value, opts, depth + 1, out);
+    }
+    indent(opts, depth, out);
+    out.push('}');
+}
+
+fn write_array(items: &[Value], opts: &SerializeOptions, depth: usize, out: &mut String) {
+    if items.is_empty() {
+        out.push_str("[]");
+        return;
     }
-    let input = std::fs::read_to_string(args[1].clone()).expect("The provided file is unreadable.");
 
-    analyse(input).map(|_| ())
+    out.push('[');
+    for (i, value) in items.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        indent(opts, depth + 1, out);
+        write_value(value, opts, depth + 1, out);
+    }
+    indent(opts, depth, out);
+    out.push(']');
 }
 
-fn analyse(raw: String) -> Result<Object, Error> {
+fn indent(opts: &SerializeOptions, depth: usize, out: &mut String) {
+    if opts.pretty {
+        out.push('\n');
+        out.push_str(&" ".repeat(opts.indent_width * depth));
+    }
+}
+
+fn analyse(raw: &str) -> Result<Object, Error> {
     let tokens = tokenize(raw)?;
+    let eof = raw.chars().count();
 
     let mut iter = tokens.into_iter();
     let json = match iter.next() {
-        Some(Token::OpenBracket) => parse_object(&mut iter),
-        Some(Token::OpenList) => parse_list(&mut iter).map(|v| vec![KV("".to_string(), v)]),
-        _ => Err(Error::MustBeginWithBracket),
+        Some(Spanned(Token::OpenBracket, _)) => parse_object(raw, &mut iter, eof),
+        Some(Spanned(Token::OpenList, _)) => {
+            parse_list(raw, &mut iter, eof).map(|v| vec![KV("".to_string(), v)])
+        }
+        Some(Spanned(_, pos)) => Err(Error::MustBeginWithBracket(Pos::locate(raw, pos))),
+        None => Err(Error::MustBeginWithBracket(Pos::locate(raw, eof))),
     }?;
 
-    if iter.next().is_none() {
-        Ok(json)
-    } else {
-        Err(Error::ExtraValue)
+    match iter.next() {
+        None => Ok(json),
+        Some(Spanned(_, pos)) => Err(Error::ExtraValue(Pos::locate(raw, pos))),
     }
 }
 
-fn parse_object(iter: &mut dyn Iterator<Item = Token>) -> Result<Object, Error> {
+fn parse_object(
+    source: &str,
+    iter: &mut dyn Iterator<Item = Spanned>,
+    eof: usize,
+) -> Result<Object, Error> {
     let mut object = Object::new();
     match iter.next() {
-        Some(t) => match t {
+        Some(Spanned(t, pos)) => match t {
             Token::CloseBracket => Ok(object),
-            Token::Comma => Err(Error::TrailingComma),
+            Token::Comma => Err(Error::TrailingComma(Pos::locate(source, pos))),
             Token::Litteral(key) => {
-                match parse_kv(key, iter) {
+                match parse_kv(source, key, iter, eof) {
                     Ok(kv) => object.push(kv),
                     Err(e) => return Err(e),
                 }
                 loop {
                     match iter.next() {
-                        Some(Token::Comma) => match iter.next() {
-                            Some(Token::Litteral(key)) => match parse_kv(key, iter) {
-                                Ok(kv) => object.push(kv),
-                                Err(e) => return Err(e),
-                            },
-                            _ => return Err(Error::TrailingComma),
+                        Some(Spanned(Token::Comma, _)) => match iter.next() {
+                            Some(Spanned(Token::Litteral(key), _)) => {
+                                match parse_kv(source, key, iter, eof) {
+                                    Ok(kv) => object.push(kv),
+                                    Err(e) => return Err(e),
+                                }
+                            }
+                            Some(Spanned(_, pos)) => {
+                                return Err(Error::TrailingComma(Pos::locate(source, pos)))
+                            }
+                            None => return Err(Error::TrailingComma(Pos::locate(source, eof))),
                         },
-                        Some(Token::CloseBracket) => return Ok(object),
-                        Some(token) => return Err(Error::SyntaxError(token, line!())),
-                        None => return Err(Error::MissingClosingBracket),
+                        Some(Spanned(Token::CloseBracket, _)) => return Ok(object),
+                        Some(Spanned(token, pos)) => {
+                            return Err(Error::SyntaxError(token, Pos::locate(source, pos)))
+                        }
+                        None => return Err(Error::MissingClosingBracket(Pos::locate(source, eof))),
                     }
                 }
             }
-            _ => Err(Error::SyntaxError(Token::OpenBracket, line!())),
+            _ => Err(Error::SyntaxError(t, Pos::locate(source, pos))),
         },
-        None => Err(Error::MissingClosingBracket),
+        None => Err(Error::MissingClosingBracket(Pos::locate(source, eof))),
     }
 }
 
-fn parse_list(iter: &mut (dyn Iterator<Item = Token>)) -> Result<Value, Error> {
+fn parse_list(
+    source: &str,
+    iter: &mut (dyn Iterator<Item = Spanned>),
+    eof: usize,
+) -> Result<Value, Error> {
     let mut values = Vec::new();
-    match parse_value(iter) {
+    match parse_value(source, iter, eof) {
         Ok(v) => values.push(v),
         Err(e) => match e {
             Error::SyntaxError(Token::CloseList, _) => return Ok(Value::Array(values)),
             _ => return Err(e),
         },
     }
-    while let Some(token) = iter.next() {
+    while let Some(Spanned(token, pos)) = iter.next() {
         match token {
-            Token::Comma => match parse_value(iter) {
+            Token::Comma => match parse_value(source, iter, eof) {
                 Ok(v) => values.push(v),
                 Err(e) => return Err(e),
             },
             Token::CloseList => return Ok(Value::Array(values)),
-            _ => return Err(Error::SyntaxError(token, line!())),
+            _ => return Err(Error::SyntaxError(token, Pos::locate(source, pos))),
         }
     }
-    return Err(Error::MissingClosingBracket);
+    Err(Error::MissingClosingBracket(Pos::locate(source, eof)))
 }
 
-fn parse_kv(key: String, iter: &mut dyn Iterator<Item = Token>) -> Result<KV, Error> {
+fn parse_kv(
+    source: &str,
+    key: String,
+    iter: &mut dyn Iterator<Item = Spanned>,
+    eof: usize,
+) -> Result<KV, Error> {
     match iter.next() {
-        Some(Token::Colon) => parse_value(iter).map(|v| KV(key, v)),
-        Some(token) => Err(Error::SyntaxError(token, line!())),
-        _ => Err(Error::MissingValue),
+        Some(Spanned(Token::Colon, _)) => parse_value(source, iter, eof).map(|v| KV(key, v)),
+        Some(Spanned(token, pos)) => Err(Error::SyntaxError(token, Pos::locate(source, pos))),
+        None => Err(Error::MissingValue(Pos::locate(source, eof))),
     }
 }
 
-fn parse_value(iter: &mut (dyn Iterator<Item = Token>)) -> Result<Value, Error> {
+fn parse_value(
+    source: &str,
+    iter: &mut (dyn Iterator<Item = Spanned>),
+    eof: usize,
+) -> Result<Value, Error> {
     match iter.next() {
-        Some(t) => match t {
-            Token::OpenBracket => parse_object(iter).map(|kvs| Value::Object(kvs)),
-            Token::Litteral(l) => {
-                if is_valid_str_value(&l) {
-                    Ok(Value::Str(l))
-                } else {
-                    Err(Error::LineBreakInLitteral)
-                }
-            }
+        Some(Spanned(t, pos)) => match t {
+            Token::OpenBracket => parse_object(source, iter, eof).map(Value::Object),
+            Token::Litteral(l) => Ok(Value::Str(l)),
             Token::True => Ok(Value::Bool(true)),
             Token::False => Ok(Value::Bool(false)),
             Token::Null => Ok(Value::Null),
             Token::Number(n) => Ok(Value::Number(n)),
-            Token::OpenList => parse_list(iter).map(|v| v),
-            _ => Err(Error::SyntaxError(t, line!())),
+            Token::OpenList => parse_list(source, iter, eof),
+            _ => Err(Error::SyntaxError(t, Pos::locate(source, pos))),
         },
-        None => Err(Error::MissingValue),
+        None => Err(Error::MissingValue(Pos::locate(source, eof))),
     }
 }
 
-fn is_valid_str_value(l: &str) -> bool {
-    let mut chars = l.chars();
-    while let Some(c) = chars.next() {
-        if c == '\n' || c == '\t' {
-            return false;
+/// Look up a value by a `jq`-lite path such as `store.items[0].name`:
+/// dot-separated object keys, with `[n]` suffixes indexing into arrays.
+/// Returns `None` if any segment is missing or the path shape doesn't
+/// match the value (e.g. indexing into an object).
+pub fn get<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut current = root;
+    for segment in path.split('.') {
+        if segment.is_empty() {
+            continue;
+        }
+        let (key, indices) = parse_path_segment(segment);
+
+        if !key.is_empty() {
+            current = match current {
+                Value::Object(kvs) => &kvs.iter().find(|KV(k, _)| k == key)?.1,
+                _ => return None,
+            };
+        }
+
+        for index in indices {
+            current = match current {
+                Value::Array(items) => items.get(index)?,
+                _ => return None,
+            };
+        }
+    }
+    Some(current)
+}
+
+/// Split a path segment like `items[0][1]` into its object key (empty if
+/// the segment starts with `[`) and its list of array indices.
+fn parse_path_segment(segment: &str) -> (&str, Vec<usize>) {
+    let bracket = segment.find('[').unwrap_or(segment.len());
+    let key = &segment[..bracket];
+
+    let mut indices = Vec::new();
+    let mut rest = &segment[bracket..];
+    while let Some(after_open) = rest.strip_prefix('[') {
+        let Some(close) = after_open.find(']') else {
+            break;
+        };
+        if let Ok(index) = after_open[..close].parse::<usize>() {
+            indices.push(index);
         }
+        rest = &after_open[close + 1..];
     }
-    true
+
+    (key, indices)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{analyse, Value, KV};
+    use crate::{analyse, get, serialize, Error, SerializeOptions, Value, KV};
 
     #[test]
     fn test_step1_valid() {
-        let json = analyse(std::fs::read_to_string("tests/step1/valid.json").unwrap()).unwrap();
+        let json = analyse(&std::fs::read_to_string("tests/step1/valid.json").unwrap()).unwrap();
 
         assert!(json.len() == 0);
     }
 
     #[test]
     fn test_step1_invalid() {
-        assert!(analyse(std::fs::read_to_string("tests/step1/invalid.json").unwrap()).is_err());
+        assert!(analyse(&std::fs::read_to_string("tests/step1/invalid.json").unwrap()).is_err());
     }
 
     #[test]
     fn test_step2_valid() {
-        let json = analyse(std::fs::read_to_string("tests/step2/valid.json").unwrap()).unwrap();
+        let json = analyse(&std::fs::read_to_string("tests/step2/valid.json").unwrap()).unwrap();
 
         assert_eq!(
             json[0],
@@ -278,7 +650,7 @@ mod tests {
 
     #[test]
     fn test_step2_valid2() {
-        let json = analyse(std::fs::read_to_string("tests/step2/valid2.json").unwrap()).unwrap();
+        let json = analyse(&std::fs::read_to_string("tests/step2/valid2.json").unwrap()).unwrap();
 
         assert_eq!(
             json[0],
@@ -292,17 +664,17 @@ mod tests {
 
     #[test]
     fn test_step2_invalid() {
-        assert!(analyse(std::fs::read_to_string("tests/step2/invalid.json").unwrap()).is_err());
+        assert!(analyse(&std::fs::read_to_string("tests/step2/invalid.json").unwrap()).is_err());
     }
 
     #[test]
     fn test_step2_invalid2() {
-        assert!(analyse(std::fs::read_to_string("tests/step2/invalid2.json").unwrap()).is_err());
+        assert!(analyse(&std::fs::read_to_string("tests/step2/invalid2.json").unwrap()).is_err());
     }
 
     #[test]
     fn test_step3_valid() {
-        let json = analyse(std::fs::read_to_string("tests/step3/valid.json").unwrap()).unwrap();
+        let json = analyse(&std::fs::read_to_string("tests/step3/valid.json").unwrap()).unwrap();
 
         assert_eq!(json[0], KV("key1".to_string(), Value::Bool(true)));
         assert_eq!(json[1], KV("key2".to_string(), Value::Bool(false)));
@@ -316,12 +688,12 @@ mod tests {
 
     #[test]
     fn test_step3_invalid() {
-        assert!(analyse(std::fs::read_to_string("tests/step3/invalid.json").unwrap()).is_err());
+        assert!(analyse(&std::fs::read_to_string("tests/step3/invalid.json").unwrap()).is_err());
     }
 
     #[test]
     fn test_step4_valid() {
-        let json = analyse(std::fs::read_to_string("tests/step4/valid.json").unwrap()).unwrap();
+        let json = analyse(&std::fs::read_to_string("tests/step4/valid.json").unwrap()).unwrap();
 
         assert_eq!(
             json[0],
@@ -334,7 +706,7 @@ mod tests {
 
     #[test]
     fn test_step4_valid2() {
-        let json = analyse(std::fs::read_to_string("tests/step4/valid2.json").unwrap()).unwrap();
+        let json = analyse(&std::fs::read_to_string("tests/step4/valid2.json").unwrap()).unwrap();
 
         assert_eq!(
             json[0],
@@ -362,7 +734,7 @@ mod tests {
 
     #[test]
     fn test_step4_invalid() {
-        assert!(analyse(std::fs::read_to_string("tests/step4/invalid.json").unwrap()).is_err());
+        assert!(analyse(&std::fs::read_to_string("tests/step4/invalid.json").unwrap()).is_err());
     }
 
     #[test]
@@ -380,7 +752,7 @@ mod tests {
             })
             .for_each(|dir_entry| {
                 assert!(
-                    analyse(std::fs::read_to_string(dir_entry.as_ref().unwrap().path()).unwrap())
+                    analyse(&std::fs::read_to_string(dir_entry.as_ref().unwrap().path()).unwrap())
                         .is_err(),
                     "Failed on file {}",
                     dir_entry.unwrap().file_name().to_str().unwrap()
@@ -390,16 +762,234 @@ mod tests {
 
     #[test]
     fn test_step5_pass1() {
-        analyse(std::fs::read_to_string("tests/step5/pass1.json").unwrap()).unwrap();
+        analyse(&std::fs::read_to_string("tests/step5/pass1.json").unwrap()).unwrap();
     }
 
     #[test]
     fn test_step5_pass2() {
-        analyse(std::fs::read_to_string("tests/step5/pass2.json").unwrap()).unwrap();
+        analyse(&std::fs::read_to_string("tests/step5/pass2.json").unwrap()).unwrap();
     }
 
     #[test]
     fn test_step5_pass3() {
-        analyse(std::fs::read_to_string("tests/step5/pass3.json").unwrap()).unwrap();
+        analyse(&std::fs::read_to_string("tests/step5/pass3.json").unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_decode_simple_escapes() {
+        let json = analyse(r#"{"key":"a\"b\\c\/d\n\t"}"#).unwrap();
+
+        assert_eq!(
+            json[0],
+            KV("key".to_string(), Value::Str("a\"b\\c/d\n\t".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_decode_unicode_escape() {
+        let json = analyse("{\"key\":\"\\u00e9\"}").unwrap();
+
+        assert_eq!(json[0], KV("key".to_string(), Value::Str("é".to_string())));
+    }
+
+    #[test]
+    fn test_decode_surrogate_pair() {
+        // U+1F600 GRINNING FACE, encoded as the surrogate pair D83D DE00.
+        let json = analyse("{\"key\":\"\\ud83d\\ude00\"}").unwrap();
+
+        assert_eq!(
+            json[0],
+            KV("key".to_string(), Value::Str("\u{1F600}".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_decode_lone_surrogate_is_error() {
+        assert!(analyse(r#"{"key":"\ud83d"}"#).is_err());
+        assert!(analyse(r#"{"key":"\ude00"}"#).is_err());
+    }
+
+    #[test]
+    fn test_decode_unknown_escape_is_error() {
+        assert!(analyse(r#"{"key":"\q"}"#).is_err());
+    }
+
+    #[test]
+    fn test_serialize_escapes_special_characters() {
+        let value = Value::Str("a\"b\\c\n".to_string());
+
+        assert_eq!(
+            serialize(&value, &SerializeOptions::default()),
+            r#""a\"b\\c\n""#
+        );
+    }
+
+    #[test]
+    fn test_serialize_compact() {
+        let value = Value::Object(vec![
+            KV("a".to_string(), Value::Number(1f64)),
+            KV("b".to_string(), Value::Array(vec![Value::Bool(true), Value::Null])),
+        ]);
+
+        assert_eq!(
+            serialize(&value, &SerializeOptions::default()),
+            r#"{"a":1,"b":[true,null]}"#
+        );
+    }
+
+    #[test]
+    fn test_serialize_pretty() {
+        let value = Value::Object(vec![KV("a".to_string(), Value::Number(1f64))]);
+
+        let opts = SerializeOptions {
+            pretty: true,
+            indent_width: 2,
+        };
+
+        assert_eq!(serialize(&value, &opts), "{\n  \"a\": 1\n}");
+    }
+
+    #[test]
+    fn test_serialize_empty_containers() {
+        let value = Value::Object(vec![
+            KV("o".to_string(), Value::Object(Vec::new())),
+            KV("l".to_string(), Value::Array(Vec::new())),
+        ]);
+
+        assert_eq!(
+            serialize(&value, &SerializeOptions::default()),
+            r#"{"o":{},"l":[]}"#
+        );
+    }
+
+    #[test]
+    fn test_serialize_integer_vs_float() {
+        assert_eq!(
+            serialize(&Value::Number(101f64), &SerializeOptions::default()),
+            "101"
+        );
+        assert_eq!(
+            serialize(&Value::Number(1.5), &SerializeOptions::default()),
+            "1.5"
+        );
+    }
+
+    #[test]
+    fn test_serialize_roundtrips_through_parse() {
+        let input = std::fs::read_to_string("tests/step4/valid2.json").unwrap();
+        let object = analyse(&input).unwrap();
+
+        let compact = serialize(&Value::Object(object), &SerializeOptions::default());
+        let reparsed = analyse(&compact).unwrap();
+
+        assert_eq!(reparsed[0], KV("key".to_string(), Value::Str("value".to_string())));
+        assert_eq!(reparsed[1], KV("key-n".to_string(), Value::Number(101f64)));
+    }
+
+    #[test]
+    fn test_error_points_at_the_right_line_and_column() {
+        let err = analyse("{\n  \"key\": tru\n}").unwrap_err();
+
+        match &err {
+            Error::SyntaxError(_, pos) | Error::UnrecognizedToken(_, pos) => {
+                assert_eq!(pos.line, 2);
+                assert_eq!(pos.col, 13);
+                assert_eq!(pos.source_line, "  \"key\": tru");
+            }
+            other => panic!("expected a position-carrying error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_error_display_includes_a_caret_snippet() {
+        let err = analyse("{\"key\" 1}").unwrap_err();
+        let rendered = format!("{err}");
+
+        assert!(rendered.contains("line 1, column 8"));
+        assert!(rendered.contains("{\"key\" 1}"));
+        assert!(rendered.ends_with('^'));
+    }
+
+    #[test]
+    fn test_number_grammar_accepts_valid_forms() {
+        let json = analyse(r#"{"a":0,"b":-0,"c":-12,"d":0.5,"e":1.25e10,"f":2E-3}"#).unwrap();
+
+        assert_eq!(json[0], KV("a".to_string(), Value::Number(0.0)));
+        assert_eq!(json[1], KV("b".to_string(), Value::Number(-0.0)));
+        assert_eq!(json[2], KV("c".to_string(), Value::Number(-12.0)));
+        assert_eq!(json[3], KV("d".to_string(), Value::Number(0.5)));
+        assert_eq!(json[4], KV("e".to_string(), Value::Number(1.25e10)));
+        assert_eq!(json[5], KV("f".to_string(), Value::Number(2e-3)));
+    }
+
+    #[test]
+    fn test_number_grammar_rejects_leading_zero() {
+        assert!(matches!(
+            analyse(r#"{"a":007}"#).unwrap_err(),
+            Error::LeadingZero(_)
+        ));
+    }
+
+    #[test]
+    fn test_number_grammar_rejects_bare_trailing_dot() {
+        assert!(matches!(
+            analyse(r#"{"a":1.}"#).unwrap_err(),
+            Error::MissingFractionDigits(_)
+        ));
+    }
+
+    #[test]
+    fn test_number_grammar_rejects_leading_dot() {
+        assert!(analyse(r#"{"a":.5}"#).is_err());
+    }
+
+    #[test]
+    fn test_number_grammar_rejects_leading_plus() {
+        assert!(analyse(r#"{"a":+5}"#).is_err());
+    }
+
+    #[test]
+    fn test_number_grammar_rejects_missing_exponent_digits() {
+        assert!(matches!(
+            analyse(r#"{"a":1e}"#).unwrap_err(),
+            Error::MissingExponentDigits(_)
+        ));
+    }
+
+    #[test]
+    fn test_get_resolves_nested_object_and_array_path() {
+        let object = analyse(
+            r#"{"store":{"items":[{"name":"a"},{"name":"b"}]},"count":2}"#,
+        )
+        .unwrap();
+        let value = Value::Object(object);
+
+        assert_eq!(
+            get(&value, "store.items[0].name"),
+            Some(&Value::Str("a".to_string()))
+        );
+        assert_eq!(
+            get(&value, "store.items[1].name"),
+            Some(&Value::Str("b".to_string()))
+        );
+        assert_eq!(get(&value, "count"), Some(&Value::Number(2.0)));
+    }
+
+    #[test]
+    fn test_get_returns_none_for_missing_key_or_out_of_bounds_index() {
+        let object = analyse(r#"{"items":[1,2]}"#).unwrap();
+        let value = Value::Object(object);
+
+        assert_eq!(get(&value, "missing"), None);
+        assert_eq!(get(&value, "items[5]"), None);
+        assert_eq!(get(&value, "items.name"), None);
+    }
+
+    #[test]
+    fn test_get_with_empty_path_returns_the_root() {
+        let object = analyse(r#"{"a":1}"#).unwrap();
+        let value = Value::Object(object);
+
+        assert_eq!(get(&value, ""), Some(&value));
     }
 }
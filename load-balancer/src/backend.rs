@@ -0,0 +1,258 @@
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// A single backend server. Tracks whether it is currently considered
+/// healthy and how many connections it is actively serving, so strategies
+/// and health checks can act on live state instead of static config.
+pub struct Backend {
+    pub addr: String,
+    pub weight: u32,
+    healthy: AtomicBool,
+    in_flight: AtomicUsize,
+}
+
+impl Backend {
+    pub fn new(addr: &str) -> Backend {
+        Backend::weighted(addr, 1)
+    }
+
+    pub fn weighted(addr: &str, weight: u32) -> Backend {
+        Backend {
+            addr: addr.to_string(),
+            weight,
+            healthy: AtomicBool::new(true),
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    pub fn set_healthy(&self, healthy: bool) {
+        self.healthy.store(healthy, Ordering::Relaxed);
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+}
+
+/// A live connection to a backend. Decrements the backend's in-flight
+/// counter on drop, so `LeastConnections` always sees an accurate count.
+pub struct Connection {
+    pub backend: Arc<Backend>,
+    pub stream: TcpStream,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.backend.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Picks the next backend to try, out of the currently healthy ones.
+pub trait Strategy: Send + Sync {
+    fn select(&self, backends: &[Arc<Backend>]) -> Option<Arc<Backend>>;
+}
+
+/// Cycles through the healthy backends in order.
+#[derive(Default)]
+pub struct RoundRobin {
+    next: AtomicUsize,
+}
+
+impl Strategy for RoundRobin {
+    fn select(&self, backends: &[Arc<Backend>]) -> Option<Arc<Backend>> {
+        let healthy: Vec<&Arc<Backend>> = backends.iter().filter(|b| b.is_healthy()).collect();
+        if healthy.is_empty() {
+            return None;
+        }
+
+        let i = self.next.fetch_add(1, Ordering::Relaxed) % healthy.len();
+        Some(healthy[i].clone())
+    }
+}
+
+/// Sends the next request to whichever healthy backend has the fewest
+/// connections currently in flight.
+#[derive(Default)]
+pub struct LeastConnections;
+
+impl Strategy for LeastConnections {
+    fn select(&self, backends: &[Arc<Backend>]) -> Option<Arc<Backend>> {
+        backends
+            .iter()
+            .filter(|b| b.is_healthy())
+            .min_by_key(|b| b.in_flight())
+            .cloned()
+    }
+}
+
+/// Round-robins, but gives backends with a higher `weight` proportionally
+/// more turns, by cycling through a pre-expanded sequence of indices.
+#[derive(Default)]
+pub struct WeightedRoundRobin {
+    next: AtomicUsize,
+}
+
+impl Strategy for WeightedRoundRobin {
+    fn select(&self, backends: &[Arc<Backend>]) -> Option<Arc<Backend>> {
+        let mut sequence = Vec::new();
+        for (i, backend) in backends.iter().enumerate() {
+            if backend.is_healthy() {
+                sequence.extend(std::iter::repeat(i).take(backend.weight.max(1) as usize));
+            }
+        }
+        if sequence.is_empty() {
+            return None;
+        }
+
+        let i = self.next.fetch_add(1, Ordering::Relaxed) % sequence.len();
+        Some(backends[sequence[i]].clone())
+    }
+}
+
+/// A pool of backends plus the strategy used to pick between them, shared
+/// across connection-handling threads and the background health checker.
+pub struct Pool {
+    pub backends: Vec<Arc<Backend>>,
+    pub strategy: Box<dyn Strategy>,
+}
+
+impl Pool {
+    pub fn new(backends: Vec<Arc<Backend>>, strategy: Box<dyn Strategy>) -> Pool {
+        Pool { backends, strategy }
+    }
+
+    /// Try to connect to a backend, retrying the next candidate (passive
+    /// failover) whenever a selection turns out to be dead. A backend that
+    /// fails to connect is marked unhealthy immediately, so later
+    /// selections skip it until the health checker confirms it's back.
+    pub fn connect(&self) -> Option<Connection> {
+        for _ in 0..self.backends.len() {
+            let backend = self.strategy.select(&self.backends)?;
+            match TcpStream::connect(&backend.addr) {
+                Ok(stream) => {
+                    backend.in_flight.fetch_add(1, Ordering::Relaxed);
+                    return Some(Connection { backend, stream });
+                }
+                Err(_) => backend.set_healthy(false),
+            }
+        }
+        None
+    }
+}
+
+/// Periodically probes every backend with a plain TCP connection and
+/// flips it back to healthy once it starts accepting connections again.
+pub fn spawn_health_checks(pool: Arc<Pool>, interval: Duration) {
+    thread::spawn(move || loop {
+        for backend in &pool.backends {
+            let reachable = TcpStream::connect(&backend.addr).is_ok();
+            backend.set_healthy(reachable);
+        }
+        thread::sleep(interval);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// Bind an ephemeral listener so a `Backend` can successfully connect
+    /// to it; returning the listener keeps the port alive for the test.
+    fn listening_backend() -> (Arc<Backend>, TcpListener) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        (Arc::new(Backend::new(&addr)), listener)
+    }
+
+    #[test]
+    fn round_robin_skips_unhealthy_backends() {
+        let a = Arc::new(Backend::new("a:1"));
+        let b = Arc::new(Backend::new("b:1"));
+        b.set_healthy(false);
+        let backends = vec![a.clone(), b];
+        let strategy = RoundRobin::default();
+
+        for _ in 0..4 {
+            assert_eq!(strategy.select(&backends).unwrap().addr, a.addr);
+        }
+    }
+
+    #[test]
+    fn round_robin_returns_none_when_all_unhealthy() {
+        let a = Arc::new(Backend::new("a:1"));
+        a.set_healthy(false);
+        let strategy = RoundRobin::default();
+        assert!(strategy.select(&[a]).is_none());
+    }
+
+    #[test]
+    fn least_connections_picks_the_least_busy_healthy_backend() {
+        let a = Arc::new(Backend::new("a:1"));
+        let b = Arc::new(Backend::new("b:1"));
+        a.in_flight.fetch_add(3, Ordering::Relaxed);
+        let backends = vec![a, b.clone()];
+
+        let picked = LeastConnections.select(&backends).unwrap();
+        assert_eq!(picked.addr, b.addr);
+    }
+
+    #[test]
+    fn weighted_round_robin_favors_higher_weight() {
+        let a = Arc::new(Backend::weighted("a:1", 3));
+        let b = Arc::new(Backend::weighted("b:1", 1));
+        let backends = vec![a.clone(), b.clone()];
+        let strategy = WeightedRoundRobin::default();
+
+        let picks: Vec<String> = (0..4)
+            .map(|_| strategy.select(&backends).unwrap().addr.clone())
+            .collect();
+        let a_picks = picks.iter().filter(|addr| **addr == a.addr).count();
+        assert_eq!(a_picks, 3);
+    }
+
+    #[test]
+    fn connection_drop_decrements_in_flight() {
+        let (backend, listener) = listening_backend();
+        backend.in_flight.fetch_add(1, Ordering::Relaxed);
+        let stream = TcpStream::connect(&backend.addr).unwrap();
+        let conn = Connection {
+            backend: backend.clone(),
+            stream,
+        };
+        drop(listener);
+
+        assert_eq!(backend.in_flight(), 1);
+        drop(conn);
+        assert_eq!(backend.in_flight(), 0);
+    }
+
+    #[test]
+    fn pool_connect_marks_unreachable_backend_unhealthy_and_retries() {
+        let dead = Arc::new(Backend::new("127.0.0.1:1"));
+        let (live, listener) = listening_backend();
+        let pool = Pool::new(
+            vec![dead.clone(), live.clone()],
+            Box::new(RoundRobin::default()),
+        );
+
+        let conn = pool.connect().unwrap();
+        assert_eq!(conn.backend.addr, live.addr);
+        assert!(!dead.is_healthy());
+        drop(listener);
+    }
+
+    #[test]
+    fn pool_connect_returns_none_when_every_backend_is_unreachable() {
+        let dead = Arc::new(Backend::new("127.0.0.1:1"));
+        let pool = Pool::new(vec![dead], Box::new(RoundRobin::default()));
+        assert!(pool.connect().is_none());
+    }
+}
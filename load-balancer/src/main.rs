@@ -1,11 +1,17 @@
 use std::io::Read;
 use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
 use std::{
     io::{BufRead, BufReader},
     net::{TcpListener, TcpStream},
     thread,
 };
 
+mod backend;
+
+use backend::{Backend, Pool, RoundRobin};
+
 #[derive(Debug)]
 struct Error(String);
 
@@ -37,9 +43,13 @@ fn read_http_request(reader: &mut impl Read) -> Result<String, Error> {
 
     // Parse Header
     let mut content_len = None;
+    let mut chunked = false;
     while let Ok(_) = reader.read_line(&mut buf) {
+        let lower = buf.to_lowercase();
+        let trimmed = lower.trim();
+
         let keyword = "content-length:";
-        if buf.to_lowercase().trim().starts_with(keyword) {
+        if trimmed.starts_with(keyword) {
             content_len = Some(
                 buf[keyword.len()..]
                     .trim()
@@ -48,6 +58,11 @@ fn read_http_request(reader: &mut impl Read) -> Result<String, Error> {
             )
         }
 
+        let keyword = "transfer-encoding:";
+        if trimmed.starts_with(keyword) && trimmed[keyword.len()..].contains("chunked") {
+            chunked = true;
+        }
+
         // If we encounter an empty line, we've reached the end of the headers
         if buf == "\r\n" || buf == "\n" {
             req.push_str("\r\n");
@@ -58,8 +73,10 @@ fn read_http_request(reader: &mut impl Read) -> Result<String, Error> {
         buf.clear();
     }
 
-    // Parse optional content
-    if let Some(len) = content_len {
+    if chunked {
+        req.push_str(&read_chunked_body(&mut reader)?);
+    } else if let Some(len) = content_len {
+        // Parse optional content
         let mut buf = vec![0u8; len];
         reader
             .read_exact(&mut buf)
@@ -71,31 +88,156 @@ fn read_http_request(reader: &mut impl Read) -> Result<String, Error> {
     Ok(req)
 }
 
-fn handle_conn(mut conn: TcpStream, server: &str) {
-    let mut serv = TcpStream::connect(server).unwrap();
+/// Read a `Transfer-Encoding: chunked` body: a sequence of `<hex length>\r\n
+/// <that many bytes>\r\n` chunks, terminated by a zero-length chunk and an
+/// optional trailer block, and return it reassembled verbatim (including
+/// the chunk framing) so the caller can forward it unchanged.
+fn read_chunked_body(reader: &mut impl BufRead) -> Result<String, Error> {
+    let mut body = String::new();
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(|e| error!(e.kind()))?;
+
+        let size_str = line.trim().split(';').next().unwrap_or("").trim();
+        let chunk_size =
+            usize::from_str_radix(size_str, 16).map_err(|_| error!("Invalid chunk size"))?;
+
+        body.push_str(&line);
+
+        if chunk_size == 0 {
+            // Trailers: read header lines until the blank line that ends them.
+            loop {
+                line.clear();
+                reader.read_line(&mut line).map_err(|e| error!(e.kind()))?;
+                body.push_str(&line);
+                if line == "\r\n" || line == "\n" {
+                    break;
+                }
+            }
+            break;
+        }
 
-    let req = read_http_request(&mut conn).unwrap();
+        let mut chunk = vec![0u8; chunk_size];
+        reader
+            .read_exact(&mut chunk)
+            .map_err(|e| error!(e.kind()))?;
+        body.push_str(&String::from_utf8(chunk).map_err(|_| error!("Body is not valid utf8"))?);
+
+        // Consume the CRLF that trails every chunk's data.
+        let mut crlf = String::new();
+        reader.read_line(&mut crlf).map_err(|e| error!(e.kind()))?;
+        body.push_str(&crlf);
+    }
+
+    Ok(body)
+}
 
-    write!(serv, "{}", req).unwrap();
+/// Forward the client's request to a backend, retrying against a different
+/// healthy backend (up to once per backend in the pool) on a connect, write,
+/// or read failure, before giving up on the client's connection entirely.
+fn handle_conn(mut conn: TcpStream, pool: &Pool) {
+    let req = match read_http_request(&mut conn) {
+        Ok(req) => req,
+        Err(e) => {
+            debug!(format!("Failed to read client request: {:?}", e));
+            return;
+        }
+    };
+
+    for _ in 0..pool.backends.len() {
+        let backend_conn = match pool.connect() {
+            Some(c) => c,
+            None => {
+                debug!("No healthy backend available, dropping connection");
+                return;
+            }
+        };
+        let mut serv = &backend_conn.stream;
+
+        if write!(serv, "{}", req).is_err() {
+            backend_conn.backend.set_healthy(false);
+            continue;
+        }
 
-    let req = read_http_request(&mut serv).unwrap();
+        match read_http_request(&mut serv) {
+            Ok(resp) => {
+                let _ = write!(conn, "{}", resp);
+                return;
+            }
+            Err(e) => {
+                debug!(format!("Backend {} failed: {:?}", backend_conn.backend.addr, e));
+                backend_conn.backend.set_healthy(false);
+            }
+        }
+    }
 
-    write!(conn, "{}", req).unwrap();
+    debug!("All backends failed for this connection, giving up");
 }
 
 fn main() -> std::io::Result<()> {
     let listener = TcpListener::bind("127.0.0.1:5050")?;
 
-    let mut round_robin = ["localhost:8080", "localhost:8081", "localhost:8082"]
-        .iter()
-        .cycle();
+    let backends = vec![
+        Arc::new(Backend::new("localhost:8080")),
+        Arc::new(Backend::new("localhost:8081")),
+        Arc::new(Backend::new("localhost:8082")),
+    ];
+    let pool = Arc::new(Pool::new(backends, Box::new(RoundRobin::default())));
+
+    backend::spawn_health_checks(pool.clone(), Duration::from_secs(5));
 
     for conn in listener.incoming() {
-        let server = *round_robin.next().unwrap();
+        let pool = pool.clone();
         let _thread_handle = thread::spawn(move || {
-            handle_conn(conn.unwrap(), server);
+            handle_conn(conn.unwrap(), &pool);
         });
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn read_http_request_with_content_length() {
+        let raw = "POST / HTTP/1.1\r\nHost: x\r\nContent-Length: 5\r\n\r\nhello";
+        let req = read_http_request(&mut Cursor::new(raw)).unwrap();
+        assert_eq!(req, "POST / HTTP/1.1\r\nHost: x\r\nContent-Length: 5\r\n\r\nhello");
+    }
+
+    #[test]
+    fn read_http_request_with_chunked_body() {
+        let raw =
+            "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n";
+        let req = read_http_request(&mut Cursor::new(raw)).unwrap();
+        assert_eq!(req, raw);
+    }
+
+    #[test]
+    fn read_chunked_body_reassembles_multiple_chunks_verbatim() {
+        let raw = "4\r\nWiki\r\n5\r\npedia\r\n0\r\n\r\n";
+        let mut reader = BufReader::new(Cursor::new(raw));
+        let body = read_chunked_body(&mut reader).unwrap();
+        assert_eq!(body, raw);
+    }
+
+    #[test]
+    fn read_chunked_body_keeps_trailers() {
+        let raw = "3\r\nfoo\r\n0\r\nX-Trailer: done\r\n\r\n";
+        let mut reader = BufReader::new(Cursor::new(raw));
+        let body = read_chunked_body(&mut reader).unwrap();
+        assert_eq!(body, raw);
+    }
+
+    #[test]
+    fn read_chunked_body_rejects_invalid_chunk_size() {
+        let raw = "zz\r\nfoo\r\n";
+        let mut reader = BufReader::new(Cursor::new(raw));
+        assert!(read_chunked_body(&mut reader).is_err());
+    }
+}
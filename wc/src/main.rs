@@ -1,12 +1,11 @@
 use std::fs;
-use std::io::{self, BufRead, Read};
+use std::io::{self, Read};
 use std::process::exit;
 
 #[derive(Debug)]
 enum ErrorMessage {
     FileUnreadable,
     UnknownOption,
-    TooManyFiles,
 }
 
 impl std::fmt::Display for ErrorMessage {
@@ -14,11 +13,11 @@ impl std::fmt::Display for ErrorMessage {
         match self {
             ErrorMessage::FileUnreadable => write!(f, "Unable to read file"),
             ErrorMessage::UnknownOption => write!(f, "Unknown option"),
-            ErrorMessage::TooManyFiles => write!(f, "Too many files are given as input"),
         }
     }
 }
 
+#[derive(PartialEq)]
 enum Mode {
     Bytes,
     Lines,
@@ -27,18 +26,18 @@ enum Mode {
 }
 
 fn usage() {
-    eprintln!("Usage : wc [options] <file>");
+    eprintln!("Usage : wc [options] <file>...");
 }
 
 struct Args {
     modes: Vec<Mode>,
-    filename: Option<String>,
+    filenames: Vec<String>,
 }
 
 impl Args {
     fn from(args: Vec<String>) -> Result<Args, ErrorMessage> {
         let mut modes: Vec<Mode> = Vec::new();
-        let mut filename = None;
+        let mut filenames = Vec::new();
         for arg in args.iter().skip(1) {
             if arg.starts_with('-') {
                 modes.push(match arg.as_str() {
@@ -49,18 +48,17 @@ impl Args {
                     _ => return Err(ErrorMessage::UnknownOption),
                 })
             } else {
-                filename = match filename {
-                    None => Some(arg.clone()),
-                    Some(_) => return Err(ErrorMessage::TooManyFiles),
-                }
+                filenames.push(arg.clone())
             }
         }
 
         if modes.is_empty() {
-            modes.push(Mode::Words)
+            modes.push(Mode::Lines);
+            modes.push(Mode::Words);
+            modes.push(Mode::Bytes);
         }
 
-        Ok(Args { modes, filename })
+        Ok(Args { modes, filenames })
     }
 }
 
@@ -87,78 +85,156 @@ fn main() {
     }
 }
 
+/// Byte/line/word/char counts for one file, computed together in a single
+/// pass so every requested `Mode` can be read out of the same struct.
+#[derive(Default, Clone, Copy)]
+struct Counts {
+    bytes: usize,
+    lines: usize,
+    words: usize,
+    chars: usize,
+}
+
+impl Counts {
+    fn from_bytes(bytes: &[u8]) -> Counts {
+        let text = String::from_utf8_lossy(bytes);
+        Counts {
+            bytes: bytes.len(),
+            lines: bytes.iter().filter(|&&b| b == b'\n').count(),
+            words: text.split_whitespace().count(),
+            chars: count_chars(bytes),
+        }
+    }
+
+    fn add(&mut self, other: &Counts) {
+        self.bytes += other.bytes;
+        self.lines += other.lines;
+        self.words += other.words;
+        self.chars += other.chars;
+    }
+
+    fn value_of(&self, mode: &Mode) -> usize {
+        match mode {
+            Mode::Bytes => self.bytes,
+            Mode::Lines => self.lines,
+            Mode::Words => self.words,
+            Mode::Chars => self.chars,
+        }
+    }
+}
+
+/// Count the unicode scalar values in `bytes`, skipping over any invalid
+/// UTF-8 sequences rather than failing outright.
+fn count_chars(mut bytes: &[u8]) -> usize {
+    let mut count = 0;
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(valid) => {
+                count += valid.chars().count();
+                break;
+            }
+            Err(e) => {
+                let valid_up_to = e.valid_up_to();
+                count += std::str::from_utf8(&bytes[..valid_up_to])
+                    .unwrap()
+                    .chars()
+                    .count();
+                let invalid_len = e.error_len().unwrap_or(1);
+                bytes = &bytes[valid_up_to + invalid_len..];
+            }
+        }
+    }
+    count
+}
+
 fn run(args: Args) -> Result<String, ErrorMessage> {
-    let input: Box<dyn BufRead> = if let Some(filepath) = args.filename {
-        let file = fs::File::open(&filepath).map_err(|_| ErrorMessage::FileUnreadable)?;
-        Box::new(io::BufReader::new(file))
+    let targets: Vec<Option<String>> = if args.filenames.is_empty() {
+        vec![None]
     } else {
-        Box::new(io::BufReader::new(io::stdin()))
+        args.filenames.iter().cloned().map(Some).collect()
     };
 
-    for mode in args.modes.iter() {
-        match mode {
-            Mode::Bytes => {
-                let mut buf = Vec::new();
-                let mut reader = input.take(usize::MAX as u64);
-                reader
-                    .read_to_end(&mut buf)
+    let mut rows = Vec::new();
+    for target in &targets {
+        let mut bytes = Vec::new();
+        match target {
+            Some(path) => {
+                let mut file = fs::File::open(path).map_err(|_| ErrorMessage::FileUnreadable)?;
+                file.read_to_end(&mut bytes)
                     .map_err(|_| ErrorMessage::FileUnreadable)?;
-                return Ok(format!("{}", buf.len()));
-            }
-            Mode::Lines => {
-                return Ok(format!("{}", input.lines().count()));
-            }
-            Mode::Words => {
-                let word_count = input.lines().fold(0, |acc, e| {
-                    acc + e
-                        .unwrap()
-                        .split_whitespace()
-                        .filter(|s| !s.is_empty())
-                        .count()
-                });
-                return Ok(format!("{}", word_count));
             }
-            Mode::Chars => {
-                return handle_chars(input);
+            None => {
+                io::stdin()
+                    .read_to_end(&mut bytes)
+                    .map_err(|_| ErrorMessage::FileUnreadable)?;
             }
         }
+        rows.push((target.clone(), Counts::from_bytes(&bytes)));
     }
 
-    Ok(String::new())
-}
+    let mut total = Counts::default();
+    for (_, counts) in &rows {
+        total.add(counts);
+    }
 
-fn handle_chars<R: BufRead>(mut reader: R) -> Result<String, ErrorMessage> {
-    let mut buf = [0; 2048];
-    let mut chars_count = 0;
-    let mut left_overs: Vec<u8> = Vec::new();
+    Ok(format_table(&args.modes, &rows, &total, rows.len() > 1))
+}
 
-    while let Ok(bytes_read) = reader.read(&mut buf) {
-        if bytes_read == 0 {
-            break;
+/// Dedupe `modes`, keeping the first occurrence of each distinct variant so
+/// repeated flags don't produce duplicate columns.
+fn unique_modes(modes: &[Mode]) -> Vec<&Mode> {
+    let mut unique: Vec<&Mode> = Vec::new();
+    for mode in modes {
+        if !unique.iter().any(|m| **m == *mode) {
+            unique.push(mode);
         }
+    }
+    unique
+}
 
-        let mut chunk = left_overs.clone();
-        chunk.extend_from_slice(&buf[..bytes_read]);
+/// Render one right-aligned row per file, plus a trailing `total` row when
+/// more than one file was given, with each column's width set by its
+/// widest value across all rows.
+fn format_table(
+    modes: &[Mode],
+    rows: &[(Option<String>, Counts)],
+    total: &Counts,
+    show_total: bool,
+) -> String {
+    let columns = unique_modes(modes);
+
+    let mut widths = vec![0usize; columns.len()];
+    let mut all_counts: Vec<&Counts> = rows.iter().map(|(_, counts)| counts).collect();
+    if show_total {
+        all_counts.push(total);
+    }
+    for counts in &all_counts {
+        for (i, mode) in columns.iter().enumerate() {
+            widths[i] = widths[i].max(counts.value_of(mode).to_string().len());
+        }
+    }
 
-        match std::str::from_utf8(&chunk) {
-            Ok(valid_str) => {
-                chars_count += valid_str.chars().count();
-                left_overs.clear();
-            }
-            Err(e) => {
-                let valid_up_to = e.valid_up_to();
-                if valid_up_to > 0 {
-                    chars_count += std::str::from_utf8(&chunk[..valid_up_to])
-                        .unwrap()
-                        .chars()
-                        .count();
-                }
-                left_overs = chunk[valid_up_to..].to_vec();
-            }
+    let format_row = |name: Option<&str>, counts: &Counts| {
+        let mut parts: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, mode)| format!("{:>width$}", counts.value_of(mode), width = widths[i]))
+            .collect();
+        if let Some(name) = name {
+            parts.push(name.to_string());
         }
+        parts.join(" ")
+    };
+
+    let mut lines: Vec<String> = rows
+        .iter()
+        .map(|(name, counts)| format_row(name.as_deref(), counts))
+        .collect();
+    if show_total {
+        lines.push(format_row(Some("total"), total));
     }
 
-    Ok(format!("{}", chars_count))
+    lines.join("\n")
 }
 
 #[cfg(test)]
@@ -169,7 +245,7 @@ mod tests {
     fn test_nofile() {
         let result = run(Args {
             modes: vec![Mode::Bytes],
-            filename: Some("pas_la.pasla".to_string()),
+            filenames: vec!["pas_la.pasla".to_string()],
         });
 
         assert!(matches!(result, Err(ErrorMessage::FileUnreadable)));
@@ -179,65 +255,79 @@ mod tests {
     fn test_c() {
         let result = run(Args {
             modes: vec![Mode::Bytes],
-            filename: Some("test.txt".to_string()),
+            filenames: vec!["test.txt".to_string()],
         });
 
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "342190".to_string());
+        assert_eq!(result.unwrap(), "342190 test.txt".to_string());
     }
 
     #[test]
     fn test_l() {
         let result = run(Args {
             modes: vec![Mode::Lines],
-            filename: Some("test.txt".to_string()),
+            filenames: vec!["test.txt".to_string()],
         });
 
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "7145".to_string());
+        assert_eq!(result.unwrap(), "7145 test.txt".to_string());
     }
 
     #[test]
     fn test_1l() {
         let result = run(Args {
             modes: vec![Mode::Lines],
-            filename: Some("1.txt".to_string()),
+            filenames: vec!["1.txt".to_string()],
         });
 
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "1".to_string());
+        assert_eq!(result.unwrap(), "1 1.txt".to_string());
     }
 
     #[test]
     fn test_0l() {
         let result = run(Args {
             modes: vec![Mode::Lines],
-            filename: Some("0.txt".to_string()),
+            filenames: vec!["0.txt".to_string()],
         });
 
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "0".to_string());
+        assert_eq!(result.unwrap(), "0 0.txt".to_string());
     }
 
     #[test]
     fn test_w() {
         let result = run(Args {
             modes: vec![Mode::Words],
-            filename: Some("test.txt".to_string()),
+            filenames: vec!["test.txt".to_string()],
         });
 
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "58164".to_string());
+        assert_eq!(result.unwrap(), "58164 test.txt".to_string());
     }
 
     #[test]
     fn test_m() {
         let result = run(Args {
             modes: vec![Mode::Chars],
-            filename: Some("test.txt".to_string()),
+            filenames: vec!["test.txt".to_string()],
+        });
+
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), "339292 test.txt".to_string());
+    }
+
+    #[test]
+    fn test_multiple_files_print_a_total_row() {
+        let result = run(Args {
+            modes: vec![Mode::Lines],
+            filenames: vec!["1.txt".to_string(), "0.txt".to_string()],
         });
 
         assert!(result.is_ok());
-        assert_eq!(result.unwrap(), "339292".to_string());
+        assert_eq!(
+            result.unwrap(),
+            "1 1.txt\n0 0.txt\n1 total".to_string()
+        );
     }
 }